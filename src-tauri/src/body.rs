@@ -0,0 +1,358 @@
+use bytes::Bytes;
+use futures_util::stream::{self, BoxStream};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_util::io::{ReaderStream, StreamReader};
+
+use crate::codec::{self, BinaryFormat};
+use crate::compression::{self, ContentCoding};
+use crate::limit::{self, SpillFile};
+use crate::proto;
+
+// 请求体分块的大小
+const CHUNK_SIZE: usize = 64 * 1024;
+// 压缩后超过此大小的请求体写入临时文件，不留在内存中
+const COMPRESSED_MEMORY_LIMIT: usize = 8 * 1024 * 1024;
+
+/// 请求体来源，大文件由 Rust 端直接从磁盘读取，不经过 IPC
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum BodySource {
+    /// 原始字节
+    #[serde(rename_all = "camelCase")]
+    Raw {
+        data: Vec<u8>,
+        content_type: Option<String>,
+    },
+    /// 磁盘文件，可只发送 `offset` 开始的 `length` 个字节
+    #[serde(rename_all = "camelCase")]
+    File {
+        path: String,
+        offset: Option<u64>,
+        length: Option<u64>,
+        content_type: Option<String>,
+    },
+    /// application/x-www-form-urlencoded 表单
+    Urlencoded { fields: Vec<FormField> },
+    /// multipart/form-data 表单，边界由 Rust 端生成并覆盖请求中的 Content-Type
+    Multipart { parts: Vec<MultipartPart> },
+    /// JSON 文本，发送前编码为 CBOR 或 MessagePack
+    Encoded { json: String, format: BinaryFormat },
+    /// JSON 文本，发送前按已注册的 .proto 编码为指定类型的 protobuf 消息
+    #[serde(rename_all = "camelCase")]
+    Protobuf { json: String, message_type: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormField {
+    pub name: String,
+    pub value: String,
+}
+
+/// multipart 的一个部分，`value` 和 `filePath` 二选一
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultipartPart {
+    pub name: String,
+    /// 文本内容
+    pub value: Option<String>,
+    /// 文件路径，内容从磁盘读取
+    pub file_path: Option<String>,
+    /// 文件名，文件部分默认使用路径中的文件名
+    pub filename: Option<String>,
+    /// 文件部分默认按扩展名推断
+    pub content_type: Option<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+/// 准备好的请求体，每次尝试都可以重新生成
+#[derive(Debug, Clone)]
+pub struct PreparedBody {
+    segments: Vec<Segment>,
+    content_type: Option<String>,
+    replace_content_type: bool,
+    // 压缩前的字节数
+    original_len: u64,
+    // 保存压缩结果的临时文件，最后一个副本释放时删除
+    temp_file: Option<Arc<SpillFile>>,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Bytes(Bytes),
+    File { path: PathBuf, offset: u64, length: u64 },
+}
+
+pub type ByteStream = BoxStream<'static, std::io::Result<Bytes>>;
+
+impl BodySource {
+    /// 检查文件、编码表单，确定请求体的内容和长度
+    pub async fn prepare(&self) -> Result<PreparedBody, String> {
+        match self {
+            BodySource::Raw { data, content_type } => {
+                let mut body = PreparedBody::bytes(data.clone());
+                body.content_type = content_type.clone();
+                Ok(body)
+            }
+            BodySource::File { path, offset, length, content_type } => {
+                let mut body = PreparedBody::segments(vec![file_segment(path, *offset, *length).await?]);
+                body.content_type = content_type.clone();
+                Ok(body)
+            }
+            BodySource::Urlencoded { fields } => {
+                let encoded = url::form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(fields.iter().map(|field| (&field.name, &field.value)))
+                    .finish();
+                let mut body = PreparedBody::bytes(encoded.into_bytes());
+                body.content_type = Some("application/x-www-form-urlencoded".to_string());
+                Ok(body)
+            }
+            BodySource::Multipart { parts } => prepare_multipart(parts).await,
+            BodySource::Encoded { json, format } => {
+                let mut body = PreparedBody::bytes(codec::encode_json(json, *format)?);
+                body.content_type = Some(format.content_type().to_string());
+                Ok(body)
+            }
+            BodySource::Protobuf { json, message_type } => {
+                let mut body = PreparedBody::bytes(proto::encode_json(json, message_type)?);
+                body.content_type = Some("application/x-protobuf".to_string());
+                Ok(body)
+            }
+        }
+    }
+}
+
+async fn file_segment(path: &str, offset: Option<u64>, length: Option<u64>) -> Result<Segment, String> {
+    let metadata = tokio::fs::metadata(path)
+        .await
+        .map_err(|e| format!("Failed to read body file {}: {}", path, e))?;
+    if !metadata.is_file() {
+        return Err(format!("Body file {} is not a regular file", path));
+    }
+
+    let file_size = metadata.len();
+    let offset = offset.unwrap_or(0);
+    if offset > file_size {
+        return Err(format!("Body offset {} is beyond the end of {} ({} bytes)", offset, path, file_size));
+    }
+    let available = file_size - offset;
+    let length = match length {
+        Some(length) if length > available => {
+            return Err(format!(
+                "Body range {}+{} is beyond the end of {} ({} bytes)",
+                offset, length, path, file_size
+            ));
+        }
+        Some(length) => length,
+        None => available,
+    };
+
+    Ok(Segment::File { path: PathBuf::from(path), offset, length })
+}
+
+/// 按 HTML 规范转义 Content-Disposition 中的名称
+fn escape_disposition(value: &str) -> String {
+    value.replace('"', "%22").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+fn generate_boundary() -> String {
+    let suffix: String = (0..24)
+        .map(|_| {
+            let index = rand::random_range(0..62);
+            (b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ"[index]) as char
+        })
+        .collect();
+    format!("----TeapotFormBoundary{}", suffix)
+}
+
+async fn prepare_multipart(parts: &[MultipartPart]) -> Result<PreparedBody, String> {
+    let boundary = generate_boundary();
+    let mut segments = Vec::with_capacity(parts.len() * 3 + 1);
+
+    for part in parts {
+        let (content, filename, content_type) = match (&part.value, &part.file_path) {
+            (Some(value), None) => (
+                Segment::Bytes(Bytes::from(value.clone().into_bytes())),
+                part.filename.clone(),
+                part.content_type.clone(),
+            ),
+            (None, Some(file_path)) => {
+                let filename = part.filename.clone().or_else(|| {
+                    Path::new(file_path).file_name().map(|name| name.to_string_lossy().to_string())
+                });
+                let content_type = part.content_type.clone().unwrap_or_else(|| {
+                    mime_guess::from_path(filename.as_deref().unwrap_or(file_path))
+                        .first_or_octet_stream()
+                        .to_string()
+                });
+                (file_segment(file_path, None, None).await?, filename, Some(content_type))
+            }
+            _ => return Err(format!("Multipart part {} must have either a value or a file path", part.name)),
+        };
+
+        let mut head = format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"", boundary, escape_disposition(&part.name));
+        if let Some(filename) = filename {
+            head.push_str(&format!("; filename=\"{}\"", escape_disposition(&filename)));
+        }
+        head.push_str("\r\n");
+        if let Some(content_type) = content_type {
+            head.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+        for (name, value) in &part.headers {
+            if [name, value].iter().any(|text| text.contains(['\r', '\n'])) || name.is_empty() || name.contains(':') {
+                return Err(format!("Invalid header {} in multipart part {}", name, part.name));
+            }
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str("\r\n");
+
+        segments.push(Segment::Bytes(Bytes::from(head.into_bytes())));
+        segments.push(content);
+        segments.push(Segment::Bytes(Bytes::from_static(b"\r\n")));
+    }
+    segments.push(Segment::Bytes(Bytes::from(format!("--{}--\r\n", boundary).into_bytes())));
+
+    let mut body = PreparedBody::segments(segments);
+    body.content_type = Some(format!("multipart/form-data; boundary={}", boundary));
+    body.replace_content_type = true;
+    Ok(body)
+}
+
+impl Segment {
+    fn len(&self) -> u64 {
+        match self {
+            Segment::Bytes(bytes) => bytes.len() as u64,
+            Segment::File { length, .. } => *length,
+        }
+    }
+
+    async fn stream(&self) -> Result<ByteStream, String> {
+        match self {
+            Segment::Bytes(bytes) => {
+                let bytes = bytes.clone();
+                let total = bytes.len();
+                let chunks = (0..total)
+                    .step_by(CHUNK_SIZE)
+                    .map(move |offset| Ok(bytes.slice(offset..(offset + CHUNK_SIZE).min(total))));
+                Ok(stream::iter(chunks).boxed())
+            }
+            Segment::File { path, offset, length } => {
+                let mut file = tokio::fs::File::open(path)
+                    .await
+                    .map_err(|e| format!("Failed to open body file {}: {}", path.display(), e))?;
+                file.seek(SeekFrom::Start(*offset))
+                    .await
+                    .map_err(|e| format!("Failed to seek body file {}: {}", path.display(), e))?;
+                Ok(ReaderStream::with_capacity(file.take(*length), CHUNK_SIZE).boxed())
+            }
+        }
+    }
+}
+
+impl PreparedBody {
+    fn segments(segments: Vec<Segment>) -> Self {
+        PreparedBody {
+            original_len: segments.iter().map(Segment::len).sum(),
+            segments,
+            content_type: None,
+            replace_content_type: false,
+            temp_file: None,
+        }
+    }
+
+    /// 内存中的请求体
+    pub fn bytes(data: Vec<u8>) -> Self {
+        PreparedBody::segments(vec![Segment::Bytes(Bytes::from(data))])
+    }
+
+    /// 实际发送的字节数（压缩后），用作 Content-Length
+    pub fn len(&self) -> u64 {
+        self.segments.iter().map(Segment::len).sum()
+    }
+
+    /// 压缩前的字节数
+    pub fn original_len(&self) -> u64 {
+        self.original_len
+    }
+
+    /// 请求体对应的 Content-Type，以及是否必须覆盖请求中已有的 Content-Type
+    pub fn content_type(&self) -> Option<(&str, bool)> {
+        self.content_type.as_deref().map(|content_type| (content_type, self.replace_content_type))
+    }
+
+    /// 以数据块流的形式读取压缩前的请求体
+    pub async fn stream(&self) -> Result<ByteStream, String> {
+        let mut streams = Vec::with_capacity(self.segments.len());
+        for segment in &self.segments {
+            streams.push(segment.stream().await?);
+        }
+        Ok(stream::iter(streams).flatten().boxed())
+    }
+
+    /// 按指定编码压缩请求体，得到长度确定的新请求体；压缩结果较大时写入临时文件，Content-Type 保持不变
+    pub async fn compress(self, coding: ContentCoding) -> Result<PreparedBody, String> {
+        let mut encoder = compression::encoder(coding, StreamReader::new(self.stream().await?));
+        let mut compressed = Vec::new();
+        let mut spill: Option<(tokio::fs::File, SpillFile)> = None;
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        loop {
+            let read = encoder
+                .read(&mut buffer)
+                .await
+                .map_err(|e| format!("Failed to compress request body: {}", e))?;
+            if read == 0 {
+                break;
+            }
+            match &mut spill {
+                Some((file, temp_file)) => file
+                    .write_all(&buffer[..read])
+                    .await
+                    .map_err(|e| format!("Failed to write {}: {}", temp_file.path().display(), e))?,
+                None => {
+                    compressed.extend_from_slice(&buffer[..read]);
+                    if compressed.len() > COMPRESSED_MEMORY_LIMIT {
+                        spill = Some(limit::create_temp_file("request", &compressed).await?);
+                        compressed = Vec::new();
+                    }
+                }
+            }
+        }
+
+        let (segment, temp_file) = match spill {
+            Some((mut file, temp_file)) => {
+                file.flush()
+                    .await
+                    .map_err(|e| format!("Failed to write {}: {}", temp_file.path().display(), e))?;
+                let length = file
+                    .metadata()
+                    .await
+                    .map_err(|e| format!("Failed to read {}: {}", temp_file.path().display(), e))?
+                    .len();
+                let segment = Segment::File { path: temp_file.path().to_path_buf(), offset: 0, length };
+                (segment, Some(Arc::new(temp_file)))
+            }
+            None => (Segment::Bytes(Bytes::from(compressed)), None),
+        };
+        Ok(PreparedBody {
+            segments: vec![segment],
+            content_type: self.content_type,
+            replace_content_type: self.replace_content_type,
+            original_len: self.original_len,
+            temp_file,
+        })
+    }
+
+    /// 直接作为 reqwest 请求体，内存中的数据不需要分块
+    pub async fn request_body(&self) -> Result<reqwest::Body, String> {
+        match self.segments.as_slice() {
+            [Segment::Bytes(bytes)] => Ok(reqwest::Body::from(bytes.clone())),
+            _ => Ok(reqwest::Body::wrap_stream(self.stream().await?)),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use bytes::Bytes;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use crate::limit::SpillFile;
+
+// 缓存的响应体总大小上限，超出时淘汰最早的响应体
+const MAX_CACHED_BYTES: usize = 256 * 1024 * 1024;
+// 同时保留的临时文件数上限，超出时淘汰最早的响应体
+const MAX_SPILL_FILES: usize = 16;
+
+struct CachedBody {
+    body: Bytes,
+    // 完整响应体所在的临时文件，随缓存项一起删除
+    spill: Option<SpillFile>,
+}
+
+#[derive(Default)]
+struct BodyCache {
+    bodies: HashMap<String, CachedBody>,
+    // 按存入顺序排列的 ID，用于淘汰
+    order: VecDeque<String>,
+    total_bytes: usize,
+    spill_files: usize,
+}
+
+static BODY_CACHE: OnceLock<Mutex<BodyCache>> = OnceLock::new();
+static NEXT_BODY_ID: AtomicU64 = AtomicU64::new(1);
+
+fn get_body_cache() -> &'static Mutex<BodyCache> {
+    BODY_CACHE.get_or_init(|| Mutex::new(BodyCache::default()))
+}
+
+impl BodyCache {
+    // 移除缓存项，临时文件随之删除
+    fn take(&mut self, body_id: &str) -> Option<CachedBody> {
+        let cached = self.bodies.remove(body_id)?;
+        self.total_bytes -= cached.body.len();
+        if cached.spill.is_some() {
+            self.spill_files -= 1;
+        }
+        Some(cached)
+    }
+
+    fn remove(&mut self, body_id: &str) -> bool {
+        let removed = self.take(body_id).is_some();
+        if removed {
+            self.order.retain(|id| id != body_id);
+        }
+        removed
+    }
+}
+
+/// 把响应体放入缓存，返回用于获取响应体的 ID
+pub fn store(body: Vec<u8>) -> Result<String, String> {
+    insert(body, None)
+}
+
+/// 把截断的响应体和保存完整响应体的临时文件一起放入缓存，释放或淘汰时删除临时文件
+pub fn store_spilled(body: Vec<u8>, spill: SpillFile) -> Result<String, String> {
+    insert(body, Some(spill))
+}
+
+fn insert(body: Vec<u8>, spill: Option<SpillFile>) -> Result<String, String> {
+    let body_id = format!("body-{}", NEXT_BODY_ID.fetch_add(1, Ordering::Relaxed));
+    let mut cache = get_body_cache().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+
+    let spill_files = usize::from(spill.is_some());
+    while cache.total_bytes + body.len() > MAX_CACHED_BYTES || cache.spill_files + spill_files > MAX_SPILL_FILES {
+        let Some(oldest) = cache.order.pop_front() else {
+            break;
+        };
+        if cache.take(&oldest).is_some() {
+            log::debug!("Evicted cached response body {}", oldest);
+        }
+    }
+
+    cache.total_bytes += body.len();
+    cache.spill_files += spill_files;
+    cache.order.push_back(body_id.clone());
+    cache.bodies.insert(body_id.clone(), CachedBody { body: Bytes::from(body), spill });
+    Ok(body_id)
+}
+
+/// 清空缓存并删除全部临时文件，应用退出时调用
+pub fn clear() {
+    if let Ok(mut cache) = get_body_cache().lock() {
+        *cache = BodyCache::default();
+    }
+}
+
+/// 读取缓存的响应体（不移出缓存）
+pub fn get(body_id: &str) -> Result<Bytes, String> {
+    let cache = get_body_cache().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+    cache
+        .bodies
+        .get(body_id)
+        .map(|cached| cached.body.clone())
+        .ok_or_else(|| format!("Response body {} not found or already released", body_id))
+}
+
+/// 以二进制形式获取缓存的响应体，可通过 offset/length 分页读取
+#[tauri::command]
+pub fn fetch_response_body(body_id: String, offset: Option<u64>, length: Option<u64>) -> Result<tauri::ipc::Response, String> {
+    let body = get(&body_id)?;
+    let start = (offset.unwrap_or(0) as usize).min(body.len());
+    let end = match length {
+        Some(length) => start.saturating_add(length as usize).min(body.len()),
+        None => body.len(),
+    };
+    Ok(tauri::ipc::Response::new(body.slice(start..end).to_vec()))
+}
+
+/// 释放缓存的响应体，同时删除对应的临时文件
+#[tauri::command]
+pub fn release_response_body(body_id: String) -> Result<(), String> {
+    let mut cache = get_body_cache().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+    if !cache.remove(&body_id) {
+        log::debug!("Response body {} was not cached", body_id);
+    }
+    Ok(())
+}
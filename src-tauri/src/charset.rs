@@ -0,0 +1,166 @@
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use serde::{Deserialize, Serialize};
+
+// 在响应体开头查找 HTML/XML 字符集声明的范围
+const DECLARATION_SCAN_BYTES: usize = 1024;
+
+/// 字符集的判断依据
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CharsetSource {
+    /// 请求中手动指定
+    Override,
+    Bom,
+    /// Content-Type 的 charset 参数
+    ContentType,
+    /// HTML `<meta>` 或 XML 声明
+    Declaration,
+    /// 没有声明，内容是合法的 UTF-8
+    Utf8,
+    /// 没有声明且不是 UTF-8，按 windows-1252 解码
+    Fallback,
+}
+
+/// 解码后的响应文本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedText {
+    pub text: String,
+    /// 使用的编码（WHATWG 名称，如 `GBK`、`Shift_JIS`）
+    pub encoding: String,
+    pub source: CharsetSource,
+    /// 内容中存在无法按该编码解码的字节（已替换为 U+FFFD）
+    pub malformed: bool,
+}
+
+/// 按名称查找编码，支持 WHATWG 定义的各种别名（如 `gb2312`、`latin1`）
+pub fn encoding_for_label(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.trim().as_bytes()).ok_or_else(|| format!("Unknown charset: {}", label))
+}
+
+fn mime_type(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase()
+}
+
+fn is_textual(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || matches!(
+            mime,
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/ecmascript"
+                | "application/x-javascript"
+                | "application/x-www-form-urlencoded"
+                | "application/graphql"
+                | "application/x-ndjson"
+                | "application/yaml"
+                | "application/x-yaml"
+        )
+}
+
+fn content_type_charset(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        Encoding::for_label(value.trim().trim_matches('"').as_bytes())
+    })
+}
+
+// 读取 `name = "value"` 中的值，`rest` 从 name 之后开始
+fn attribute_value(rest: &[u8]) -> Option<&[u8]> {
+    let rest = rest.trim_ascii_start().strip_prefix(b"=")?.trim_ascii_start();
+    let (rest, quote) = match rest.first() {
+        Some(&quote @ (b'"' | b'\'')) => (&rest[1..], Some(quote)),
+        _ => (rest, None),
+    };
+    let end = rest
+        .iter()
+        .position(|&b| match quote {
+            Some(quote) => b == quote,
+            None => !(b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':')),
+        })
+        .unwrap_or(rest.len());
+    Some(&rest[..end]).filter(|value| !value.is_empty())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// 查找 XML 声明的 encoding 或 HTML `<meta>` 中的 charset
+fn declared_charset(body: &[u8]) -> Option<&'static Encoding> {
+    let head = body[..body.len().min(DECLARATION_SCAN_BYTES)].to_ascii_lowercase();
+    let trimmed = head.trim_ascii_start();
+
+    if trimmed.starts_with(b"<?xml") {
+        let declaration = &trimmed[..find(trimmed, b"?>")?];
+        let start = find(declaration, b"encoding")? + b"encoding".len();
+        return Encoding::for_label(attribute_value(&declaration[start..])?);
+    }
+
+    let mut rest = &head[..];
+    while let Some(start) = find(rest, b"<meta") {
+        let tag = &rest[start..];
+        let tag = &tag[..tag.iter().position(|&b| b == b'>').unwrap_or(tag.len())];
+        // 同时覆盖 <meta charset=...> 和 http-equiv 的 content="text/html; charset=..."
+        if let Some(position) = find(tag, b"charset")
+            && let Some(value) = attribute_value(&tag[position + b"charset".len()..])
+            && let Some(encoding) = Encoding::for_label(value)
+        {
+            // HTML 规定 <meta> 声明的 UTF-16 按 UTF-8 处理
+            return Some(encoding.output_encoding());
+        }
+        rest = &rest[start + b"<meta".len()..];
+    }
+    None
+}
+
+// 截断的响应体末尾可能只有半个字符，不影响判断
+fn is_utf8(body: &[u8], truncated: bool) -> bool {
+    match std::str::from_utf8(body) {
+        Ok(_) => true,
+        Err(e) => truncated && e.error_len().is_none(),
+    }
+}
+
+/// 按手动指定、BOM、Content-Type、HTML/XML 声明的顺序确定字符集并解码为 UTF-8 文本，
+/// 非文本内容返回 None
+pub fn decode_text(
+    body: &[u8],
+    truncated: bool,
+    content_type: Option<&str>,
+    charset: Option<&'static Encoding>,
+) -> Option<DecodedText> {
+    let declared = content_type.and_then(content_type_charset);
+    let (encoding, source) = if let Some(encoding) = charset {
+        (encoding, CharsetSource::Override)
+    } else if declared.is_none() && content_type.is_some_and(|content_type| !is_textual(&mime_type(content_type))) {
+        return None;
+    } else if let Some((encoding, _)) = Encoding::for_bom(body) {
+        (encoding, CharsetSource::Bom)
+    } else if let Some(encoding) = declared {
+        (encoding, CharsetSource::ContentType)
+    } else if let Some(encoding) = declared_charset(body) {
+        (encoding, CharsetSource::Declaration)
+    } else if is_utf8(body, truncated) {
+        (UTF_8, CharsetSource::Utf8)
+    } else if content_type.is_some() {
+        (WINDOWS_1252, CharsetSource::Fallback)
+    } else {
+        // 没有 Content-Type 又不是 UTF-8，多半是二进制内容
+        return None;
+    };
+
+    let (text, malformed) = encoding.decode_with_bom_removal(body);
+    Some(DecodedText {
+        text: text.into_owned(),
+        encoding: encoding.name().to_string(),
+        source,
+        malformed,
+    })
+}
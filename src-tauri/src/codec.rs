@@ -0,0 +1,177 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value as Json};
+
+use crate::body_cache;
+
+/// 可与 JSON 互相转换的二进制格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BinaryFormat {
+    Cbor,
+    MessagePack,
+}
+
+impl BinaryFormat {
+    /// 按 Content-Type 判断格式
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        match mime.as_str() {
+            "application/cbor" => Some(BinaryFormat::Cbor),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Some(BinaryFormat::MessagePack)
+            }
+            _ if mime.ends_with("+cbor") => Some(BinaryFormat::Cbor),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            BinaryFormat::Cbor => "application/cbor",
+            BinaryFormat::MessagePack => "application/msgpack",
+        }
+    }
+}
+
+/// 把 JSON 文本编码为 CBOR 或 MessagePack
+pub fn encode_json(json: &str, format: BinaryFormat) -> Result<Vec<u8>, String> {
+    let value: Json = serde_json::from_str(json).map_err(|e| format!("Invalid JSON body: {}", e))?;
+    match format {
+        BinaryFormat::Cbor => serde_cbor::to_vec(&value).map_err(|e| format!("Failed to encode CBOR: {}", e)),
+        BinaryFormat::MessagePack => {
+            let mut data = Vec::new();
+            rmpv::encode::write_value(&mut data, &json_to_msgpack(&value))
+                .map_err(|e| format!("Failed to encode MessagePack: {}", e))?;
+            Ok(data)
+        }
+    }
+}
+
+/// 把 CBOR 或 MessagePack 解码为格式化的 JSON 文本，
+/// 二进制数据转为 base64 字符串，非字符串的键转为字符串
+pub fn decode_to_json(data: &[u8], format: BinaryFormat) -> Result<String, String> {
+    let value = match format {
+        BinaryFormat::Cbor => {
+            let value: serde_cbor::Value =
+                serde_cbor::from_slice(data).map_err(|e| format!("Failed to decode CBOR: {}", e))?;
+            cbor_to_json(value)
+        }
+        BinaryFormat::MessagePack => {
+            let mut reader = data;
+            let value = rmpv::decode::read_value(&mut reader).map_err(|e| format!("Failed to decode MessagePack: {}", e))?;
+            if !reader.is_empty() {
+                return Err(format!("Failed to decode MessagePack: {} trailing bytes", reader.len()));
+            }
+            msgpack_to_json(value)
+        }
+    };
+    serde_json::to_string_pretty(&value).map_err(|e| format!("Failed to format JSON: {}", e))
+}
+
+fn float_to_json(value: f64) -> Json {
+    // NaN 和无穷大在 JSON 中无法表示
+    Number::from_f64(value).map_or(Json::Null, Json::Number)
+}
+
+// JSON 对象的键只能是字符串
+fn key_to_string(key: Json) -> String {
+    match key {
+        Json::String(key) => key,
+        key => key.to_string(),
+    }
+}
+
+fn cbor_to_json(value: serde_cbor::Value) -> Json {
+    use serde_cbor::Value;
+    match value {
+        Value::Null => Json::Null,
+        Value::Bool(value) => Json::Bool(value),
+        Value::Integer(value) => match (i64::try_from(value), u64::try_from(value)) {
+            (Ok(value), _) => Json::from(value),
+            (_, Ok(value)) => Json::from(value),
+            // 超出 64 位的整数保留为字符串
+            _ => Json::String(value.to_string()),
+        },
+        Value::Float(value) => float_to_json(value),
+        Value::Bytes(bytes) => Json::String(BASE64.encode(bytes)),
+        Value::Text(text) => Json::String(text),
+        Value::Array(items) => Json::Array(items.into_iter().map(cbor_to_json).collect()),
+        Value::Map(entries) => Json::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key_to_string(cbor_to_json(key)), cbor_to_json(value)))
+                .collect::<Map<_, _>>(),
+        ),
+        // 标签（如日期 0/1）只保留内容
+        Value::Tag(_, value) => cbor_to_json(*value),
+        _ => Json::Null,
+    }
+}
+
+fn msgpack_to_json(value: rmpv::Value) -> Json {
+    use rmpv::Value;
+    match value {
+        Value::Nil => Json::Null,
+        Value::Boolean(value) => Json::Bool(value),
+        Value::Integer(value) => match (value.as_i64(), value.as_u64()) {
+            (Some(value), _) => Json::from(value),
+            (_, Some(value)) => Json::from(value),
+            _ => Json::Null,
+        },
+        Value::F32(value) => float_to_json(f64::from(value)),
+        Value::F64(value) => float_to_json(value),
+        Value::String(text) => match text.into_str() {
+            Some(text) => Json::String(text),
+            None => Json::Null,
+        },
+        Value::Binary(bytes) => Json::String(BASE64.encode(bytes)),
+        Value::Array(items) => Json::Array(items.into_iter().map(msgpack_to_json).collect()),
+        Value::Map(entries) => Json::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key_to_string(msgpack_to_json(key)), msgpack_to_json(value)))
+                .collect::<Map<_, _>>(),
+        ),
+        // 扩展类型保留类型编号和 base64 编码的数据
+        Value::Ext(kind, data) => serde_json::json!({ "ext": kind, "data": BASE64.encode(data) }),
+    }
+}
+
+fn json_to_msgpack(value: &Json) -> rmpv::Value {
+    use rmpv::Value;
+    match value {
+        Json::Null => Value::Nil,
+        Json::Bool(value) => Value::Boolean(*value),
+        Json::Number(number) => {
+            if let Some(value) = number.as_u64() {
+                Value::from(value)
+            } else if let Some(value) = number.as_i64() {
+                Value::from(value)
+            } else {
+                Value::F64(number.as_f64().unwrap_or_default())
+            }
+        }
+        Json::String(text) => Value::from(text.as_str()),
+        Json::Array(items) => Value::Array(items.iter().map(json_to_msgpack).collect()),
+        Json::Object(entries) => Value::Map(
+            entries
+                .iter()
+                .map(|(key, value)| (Value::from(key.as_str()), json_to_msgpack(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// 把 JSON 请求体编码为 CBOR 或 MessagePack，以二进制形式返回
+#[tauri::command]
+pub fn encode_json_body(json: String, format: BinaryFormat) -> Result<tauri::ipc::Response, String> {
+    Ok(tauri::ipc::Response::new(encode_json(&json, format)?))
+}
+
+/// 把缓存的 CBOR 或 MessagePack 响应体（binary_body 模式返回的 body_id）解码为格式化的 JSON
+#[tauri::command]
+pub fn decode_binary_body(body_id: String, format: BinaryFormat) -> Result<String, String> {
+    decode_to_json(&body_cache::get(&body_id)?, format)
+}
@@ -0,0 +1,148 @@
+use async_compression::Level;
+use async_compression::tokio::bufread::{
+    BrotliDecoder, BrotliEncoder, GzipDecoder, GzipEncoder, ZlibDecoder, ZlibEncoder, ZstdDecoder, ZstdEncoder,
+};
+use reqwest::header::{CONTENT_ENCODING, HeaderMap};
+use serde::{Deserialize, Serialize};
+use std::pin::Pin;
+use tokio::io::{AsyncBufRead, AsyncRead, BufReader};
+
+// 请求体的 zstd 压缩级别
+const ZSTD_LEVEL: i32 = 3;
+// 请求体的 brotli 压缩质量，默认的 11 对流式上传太慢
+const BROTLI_QUALITY: i32 = 5;
+
+/// HTTP 内容编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentCoding {
+    Gzip,
+    Deflate,
+    Br,
+    Zstd,
+}
+
+impl ContentCoding {
+    pub const ALL: [ContentCoding; 4] = [
+        ContentCoding::Gzip,
+        ContentCoding::Deflate,
+        ContentCoding::Br,
+        ContentCoding::Zstd,
+    ];
+
+    pub fn token(self) -> &'static str {
+        match self {
+            ContentCoding::Gzip => "gzip",
+            ContentCoding::Deflate => "deflate",
+            ContentCoding::Br => "br",
+            ContentCoding::Zstd => "zstd",
+        }
+    }
+
+    fn from_token(token: &str) -> Option<Self> {
+        match token.to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(ContentCoding::Gzip),
+            "deflate" => Some(ContentCoding::Deflate),
+            "br" => Some(ContentCoding::Br),
+            "zstd" => Some(ContentCoding::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// 响应内容编码协商
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EncodingOptions {
+    /// Accept-Encoding 中声明接受的编码，为空时只接受未压缩的响应
+    pub accept: Vec<ContentCoding>,
+    /// 在响应中同时返回解码前的原始响应体
+    pub include_raw_body: bool,
+}
+
+impl Default for EncodingOptions {
+    fn default() -> Self {
+        EncodingOptions {
+            accept: ContentCoding::ALL.to_vec(),
+            include_raw_body: false,
+        }
+    }
+}
+
+impl EncodingOptions {
+    /// Accept-Encoding 请求头的值
+    pub fn accept_encoding(&self) -> String {
+        if self.accept.is_empty() {
+            return "identity".to_string();
+        }
+        self.accept.iter().map(|coding| coding.token()).collect::<Vec<_>>().join(", ")
+    }
+}
+
+/// 响应体解码器，支持多重编码（如 `Content-Encoding: gzip, br`）
+pub struct Decoder {
+    // 与 Content-Encoding 中的顺序相同，解码时从后往前
+    codings: Vec<ContentCoding>,
+}
+
+impl Decoder {
+    /// 根据 Content-Encoding 创建解码器，未编码或包含不支持的编码时返回 None
+    pub fn for_response(headers: &HeaderMap) -> Option<Decoder> {
+        let mut codings = Vec::new();
+        for value in headers.get_all(CONTENT_ENCODING) {
+            for token in value.to_str().ok()?.split(',').map(str::trim) {
+                if token.is_empty() || token.eq_ignore_ascii_case("identity") {
+                    continue;
+                }
+                match ContentCoding::from_token(token) {
+                    Some(coding) => codings.push(coding),
+                    None => {
+                        log::debug!("Unsupported content encoding {}, returning body as is", token);
+                        return None;
+                    }
+                }
+            }
+        }
+        if codings.is_empty() {
+            return None;
+        }
+        Some(Decoder { codings })
+    }
+
+    /// 在编码后的数据上套上解码器，读取多少解码多少，不会一次展开整块数据
+    pub fn reader<'a>(&self, input: impl AsyncBufRead + Send + 'a) -> Pin<Box<dyn AsyncRead + Send + 'a>> {
+        let mut reader: Pin<Box<dyn AsyncRead + Send + 'a>> = Box::pin(input);
+        for &coding in self.codings.iter().rev() {
+            reader = decode_stage(coding, BufReader::new(reader));
+        }
+        reader
+    }
+
+    pub fn decode_error(&self, error: impl std::fmt::Display) -> String {
+        let codings: Vec<&str> = self.codings.iter().map(|coding| coding.token()).collect();
+        format!("Failed to decode {} response body: {}", codings.join(", "), error)
+    }
+}
+
+fn decode_stage<'a>(coding: ContentCoding, input: impl AsyncBufRead + Send + 'a) -> Pin<Box<dyn AsyncRead + Send + 'a>> {
+    match coding {
+        ContentCoding::Gzip => {
+            let mut decoder = GzipDecoder::new(input);
+            decoder.multiple_members(true);
+            Box::pin(decoder)
+        }
+        ContentCoding::Deflate => Box::pin(ZlibDecoder::new(input)),
+        ContentCoding::Br => Box::pin(BrotliDecoder::new(input)),
+        ContentCoding::Zstd => Box::pin(ZstdDecoder::new(input)),
+    }
+}
+
+/// 在请求体数据上套上压缩器，边读取边压缩
+pub fn encoder<'a>(coding: ContentCoding, input: impl AsyncBufRead + Send + 'a) -> Pin<Box<dyn AsyncRead + Send + 'a>> {
+    match coding {
+        ContentCoding::Gzip => Box::pin(GzipEncoder::new(input)),
+        ContentCoding::Deflate => Box::pin(ZlibEncoder::new(input)),
+        ContentCoding::Br => Box::pin(BrotliEncoder::with_quality(input, Level::Precise(BROTLI_QUALITY))),
+        ContentCoding::Zstd => Box::pin(ZstdEncoder::with_quality(input, Level::Precise(ZSTD_LEVEL))),
+    }
+}
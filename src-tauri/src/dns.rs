@@ -0,0 +1,115 @@
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+
+/// 主机名到地址的覆盖规则（等价于 curl --resolve）
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolveOverride {
+    /// 主机名，支持 `*.example.com` 形式的通配符，`*` 匹配所有主机
+    pub host: String,
+    /// 目标 IP 地址，IPv6 可写作 `::1` 或 `[::1]`
+    pub addresses: Vec<String>,
+}
+
+/// 主机名是否匹配规则，返回匹配的精确程度（精确匹配最高，`*` 最低）
+pub fn match_host(pattern: &str, host: &str) -> Option<usize> {
+    let pattern = pattern.trim().trim_end_matches('.').to_ascii_lowercase();
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    if pattern == host {
+        Some(usize::MAX)
+    } else if pattern == "*" {
+        Some(0)
+    } else if let Some(suffix) = pattern.strip_prefix("*.") {
+        let is_subdomain = host.len() > suffix.len() + 1
+            && host.ends_with(suffix)
+            && host[..host.len() - suffix.len()].ends_with('.');
+        is_subdomain.then_some(suffix.len())
+    } else {
+        None
+    }
+}
+
+/// 在系统 DNS 之前应用覆盖规则的解析器
+pub struct OverrideResolver {
+    rules: Vec<(String, Vec<IpAddr>)>,
+    // 主机别名：解析别名时改为解析目标主机（用于覆盖 SNI）
+    alias: Option<(String, String)>,
+}
+
+impl OverrideResolver {
+    pub fn new(overrides: &[ResolveOverride]) -> Result<Self, String> {
+        let mut rules = Vec::with_capacity(overrides.len());
+        for rule in overrides {
+            let host = rule.host.trim().trim_end_matches('.').to_ascii_lowercase();
+            if host.is_empty() {
+                return Err("DNS override host cannot be empty".to_string());
+            }
+
+            let mut addresses = Vec::with_capacity(rule.addresses.len());
+            for address in &rule.addresses {
+                let trimmed = address.trim().trim_start_matches('[').trim_end_matches(']');
+                let ip = trimmed
+                    .parse::<IpAddr>()
+                    .map_err(|e| format!("Invalid DNS override address {} for {}: {}", address, rule.host, e))?;
+                addresses.push(ip);
+            }
+            if addresses.is_empty() {
+                return Err(format!("DNS override for {} has no addresses", rule.host));
+            }
+
+            rules.push((host, addresses));
+        }
+        Ok(OverrideResolver { rules, alias: None })
+    }
+
+    /// 解析 `alias` 时使用 `target` 的地址
+    pub fn with_alias(mut self, alias: &str, target: &str) -> Self {
+        self.alias = Some((alias.to_ascii_lowercase(), target.to_ascii_lowercase()));
+        self
+    }
+
+    /// 精确匹配优先，其次是最长的通配符后缀
+    fn lookup(&self, host: &str) -> Option<&[IpAddr]> {
+        let mut best: Option<(usize, &[IpAddr])> = None;
+        for (pattern, addresses) in &self.rules {
+            let Some(score) = match_host(pattern, host) else {
+                continue;
+            };
+
+            if best.is_none_or(|(best_score, _)| score > best_score) {
+                best = Some((score, addresses.as_slice()));
+            }
+        }
+        best.map(|(_, addresses)| addresses)
+    }
+}
+
+impl Resolve for OverrideResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let mut host = name.as_str().trim_end_matches('.').to_ascii_lowercase();
+        if let Some((alias, target)) = &self.alias
+            && *alias == host
+        {
+            log::debug!("DNS alias: {} -> {}", host, target);
+            host = target.clone();
+        }
+
+        // 端口为 0 时 reqwest 会替换为 URL 中的端口
+        if let Some(addresses) = self.lookup(&host) {
+            let addrs: Vec<SocketAddr> = addresses.iter().map(|ip| SocketAddr::new(*ip, 0)).collect();
+            log::debug!("DNS override: {} -> {:?}", host, addresses);
+            return Box::pin(async move { Ok(Box::new(addrs.into_iter()) as Addrs) });
+        }
+
+        // 别名目标可能是 IP 地址
+        if let Ok(ip) = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+            return Box::pin(async move { Ok(Box::new(std::iter::once(SocketAddr::new(ip, 0))) as Addrs) });
+        }
+
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
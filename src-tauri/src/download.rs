@@ -0,0 +1,246 @@
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_LENGTH, CONTENT_RANGE, ETAG, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::progress::DownloadReporter;
+use crate::throttle::DownloadPacer;
+
+// 默认返回的预览字节数
+const DEFAULT_PREVIEW_BYTES: usize = 4096;
+
+/// 把响应体直接写入文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadOptions {
+    pub path: String,
+    /// 文件已存在时从已有长度继续下载
+    #[serde(default)]
+    pub resume: bool,
+    /// 在响应中返回文件开头多少字节作为预览，默认 4096
+    pub preview_bytes: Option<usize>,
+    /// 收到响应头后写入文件的超时（毫秒），默认不限制；总超时只覆盖到收到响应头，读超时仍然生效
+    pub timeout: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadResult {
+    pub path: String,
+    /// 本次写入的字节数
+    pub bytes_written: u64,
+    /// 下载完成后文件的大小
+    pub file_size: u64,
+    /// 完整资源的大小，服务端未告知时为 None
+    pub total_size: Option<u64>,
+    /// 是否接在已有内容之后续传
+    pub resumed: bool,
+}
+
+/// 续传请求：从 `offset` 开始请求，`if_range` 为上次下载时记录的 ETag 或 Last-Modified
+#[derive(Debug, Clone)]
+pub struct ResumeRequest {
+    pub offset: u64,
+    pub if_range: String,
+}
+
+/// 未完成下载的校验信息，保存在下载文件旁的 `.resume` 文件中
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResumeState {
+    url: String,
+    validator: String,
+}
+
+fn resume_state_path(path: &str) -> PathBuf {
+    PathBuf::from(format!("{}.resume", path))
+}
+
+/// 根据已下载的内容确定续传起点；没有记录校验值时无法确认资源未变，从头下载
+pub async fn resume_request(options: &DownloadOptions, url: &str) -> Option<ResumeRequest> {
+    if !options.resume {
+        return None;
+    }
+    let offset = tokio::fs::metadata(&options.path).await.ok()?.len();
+    if offset == 0 {
+        return None;
+    }
+
+    let if_range = tokio::fs::read(resume_state_path(&options.path))
+        .await
+        .ok()
+        .and_then(|data| serde_json::from_slice::<ResumeState>(&data).ok())
+        .filter(|state| state.url == url)
+        .map(|state| state.validator)?;
+    Some(ResumeRequest { offset, if_range })
+}
+
+/// 可用于 If-Range 的校验值：强 ETag 优先，其次是 Last-Modified
+fn resume_validator(response: &reqwest::Response) -> Option<String> {
+    let headers = response.headers();
+    headers
+        .get(ETAG)
+        .and_then(|value| value.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| headers.get(LAST_MODIFIED).and_then(|value| value.to_str().ok()))
+        .map(|value| value.to_string())
+}
+
+/// 解析 `Content-Range: bytes start-end/total`，返回 (start, total)
+fn parse_content_range(response: &reqwest::Response) -> Option<(u64, Option<u64>)> {
+    let value = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let range = value.trim().strip_prefix("bytes ")?;
+    let (span, total) = range.split_once('/')?;
+    let (start, _) = span.split_once('-')?;
+    Some((start.trim().parse().ok()?, total.trim().parse().ok()))
+}
+
+/// 续传时文件已经完整：服务端返回 416 且 `Content-Range: bytes */total` 与已有长度一致
+pub fn is_complete(response: &reqwest::Response, resume: &ResumeRequest) -> bool {
+    response.status() == StatusCode::RANGE_NOT_SATISFIABLE
+        && response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().strip_prefix("bytes */"))
+            .and_then(|total| total.trim().parse::<u64>().ok())
+            == Some(resume.offset)
+}
+
+async fn read_preview(path: &Path, limit: usize) -> Result<Vec<u8>, String> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut preview = Vec::with_capacity(limit.min(DEFAULT_PREVIEW_BYTES));
+    file.take(limit as u64)
+        .read_to_end(&mut preview)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(preview)
+}
+
+/// 把响应体流式写入文件，返回下载结果和文件开头的预览
+pub async fn write_to_file(
+    mut response: reqwest::Response,
+    options: &DownloadOptions,
+    url: &str,
+    resume: Option<&ResumeRequest>,
+    mut reporter: Option<DownloadReporter>,
+    pacer: &mut DownloadPacer,
+) -> Result<(DownloadResult, Vec<u8>), String> {
+    let path = Path::new(&options.path);
+    let state_path = resume_state_path(&options.path);
+
+    // 已经下载完整，不再写入文件
+    if let Some(resume) = resume
+        && is_complete(&response, resume)
+    {
+        if let Some(reporter) = &mut reporter {
+            reporter.report(resume.offset, Some(resume.offset), true);
+        }
+        let _ = tokio::fs::remove_file(&state_path).await;
+        let preview = read_preview(path, options.preview_bytes.unwrap_or(DEFAULT_PREVIEW_BYTES)).await?;
+        let result = DownloadResult {
+            path: options.path.clone(),
+            bytes_written: 0,
+            file_size: resume.offset,
+            total_size: Some(resume.offset),
+            resumed: true,
+        };
+        return Ok((result, preview));
+    }
+
+    // 206 时在已有内容之后追加，其他成功状态（服务端忽略了 Range 或 If-Range 不匹配）从头写入
+    let (offset, total_size) = if response.status() == StatusCode::PARTIAL_CONTENT {
+        let (start, total) = parse_content_range(&response).ok_or("Partial response without a valid Content-Range")?;
+        match resume {
+            Some(resume) if resume.offset == start => (start, total),
+            _ if start == 0 => (0, total),
+            _ => return Err(format!("Unexpected Content-Range starting at {}", start)),
+        }
+    } else {
+        let length = response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+        (0, length)
+    };
+
+    let mut file = if offset > 0 {
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .await
+            .map_err(|e| format!("Failed to open {}: {}", options.path, e))?;
+        file.set_len(offset)
+            .await
+            .map_err(|e| format!("Failed to truncate {}: {}", options.path, e))?;
+        tokio::io::AsyncSeekExt::seek(&mut file, std::io::SeekFrom::Start(offset))
+            .await
+            .map_err(|e| format!("Failed to seek {}: {}", options.path, e))?;
+        file
+    } else {
+        tokio::fs::File::create(path)
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", options.path, e))?
+    };
+
+    // 记录校验值，下载中断后可以安全续传
+    if let Some(validator) = resume_validator(&response) {
+        let state = ResumeState { url: url.to_string(), validator };
+        if let Ok(data) = serde_json::to_vec(&state)
+            && let Err(e) = tokio::fs::write(&state_path, data).await
+        {
+            log::warn!("Failed to save resume state for {}: {}", options.path, e);
+        }
+    }
+
+    let mut bytes_written = 0u64;
+    if let Some(reporter) = &mut reporter {
+        reporter.report(offset, total_size, false);
+    }
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => {
+                let _ = file.flush().await;
+                return Err(format!(
+                    "Download interrupted after {} bytes, resume to continue: {}",
+                    offset + bytes_written,
+                    e
+                ));
+            }
+        };
+
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write {}: {}", options.path, e))?;
+        bytes_written += chunk.len() as u64;
+        if let Some(reporter) = &mut reporter {
+            reporter.report(offset + bytes_written, total_size, false);
+        }
+        pacer.consume(chunk.len()).await;
+    }
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", options.path, e))?;
+
+    let file_size = offset + bytes_written;
+    if let Some(reporter) = &mut reporter {
+        reporter.report(file_size, total_size, true);
+    }
+    let _ = tokio::fs::remove_file(&state_path).await;
+
+    let preview = read_preview(path, options.preview_bytes.unwrap_or(DEFAULT_PREVIEW_BYTES)).await?;
+    let result = DownloadResult {
+        path: options.path.clone(),
+        bytes_written,
+        file_size,
+        total_size,
+        resumed: offset > 0,
+    };
+    Ok((result, preview))
+}
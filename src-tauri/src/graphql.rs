@@ -0,0 +1,550 @@
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as Json};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::http_client::{self, client_config_or_global, timeout_duration, ClientConfig, HttpRequestConfig};
+use crate::ws::{self, WsStream};
+
+/// 订阅消息事件名
+pub const GRAPHQL_SUBSCRIPTION_EVENT: &str = "graphql-subscription";
+
+// 等待 connection_ack 的默认超时
+const ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 单个连接上只有一个订阅，使用固定的操作 ID
+const OPERATION_ID: &str = "1";
+
+/// graphql-js 的标准内省查询
+const INTROSPECTION_QUERY: &str = r#"query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types { ...FullType }
+    directives {
+      name
+      description
+      locations
+      args { ...InputValue }
+    }
+  }
+}
+
+fragment FullType on __Type {
+  kind
+  name
+  description
+  fields(includeDeprecated: true) {
+    name
+    description
+    args { ...InputValue }
+    type { ...TypeRef }
+    isDeprecated
+    deprecationReason
+  }
+  inputFields { ...InputValue }
+  interfaces { ...TypeRef }
+  enumValues(includeDeprecated: true) {
+    name
+    description
+    isDeprecated
+    deprecationReason
+  }
+  possibleTypes { ...TypeRef }
+}
+
+fragment InputValue on __InputValue {
+  name
+  description
+  type { ...TypeRef }
+  defaultValue
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType {
+    kind
+    name
+    ofType {
+      kind
+      name
+      ofType {
+        kind
+        name
+        ofType {
+          kind
+          name
+          ofType {
+            kind
+            name
+            ofType {
+              kind
+              name
+              ofType { kind name }
+            }
+          }
+        }
+      }
+    }
+  }
+}"#;
+
+// 各端点内省得到的 schema（`__schema` 对象），同一端点按请求头区分（不同身份可能看到不同的 schema）
+static SCHEMA_CACHE: OnceLock<Mutex<HashMap<String, HashMap<u64, Json>>>> = OnceLock::new();
+// 进行中和正在建立的订阅
+static SUBSCRIPTIONS: OnceLock<Mutex<HashMap<String, ActiveSubscription>>> = OnceLock::new();
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+struct ActiveSubscription {
+    /// 发送信号即可停止
+    stop: oneshot::Sender<()>,
+    /// 区分先后使用同一 subscription_id 的订阅
+    generation: u64,
+}
+
+fn get_schema_cache() -> &'static Mutex<HashMap<String, HashMap<u64, Json>>> {
+    SCHEMA_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 请求头的哈希，头名称不区分大小写、与顺序无关
+fn headers_key(headers: Option<&HashMap<String, String>>) -> u64 {
+    let normalized: BTreeMap<String, &str> = headers
+        .into_iter()
+        .flatten()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value.as_str()))
+        .collect();
+    let mut hasher = DefaultHasher::new();
+    normalized.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn get_subscriptions() -> &'static Mutex<HashMap<String, ActiveSubscription>> {
+    SUBSCRIPTIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 登记订阅并占用 subscription_id，ID 已被占用时返回错误
+fn register(subscription_id: &str, stop: oneshot::Sender<()>) -> Result<u64, String> {
+    let mut subscriptions = get_subscriptions().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+    if subscriptions.contains_key(subscription_id) {
+        return Err(format!("Subscription {} is already active", subscription_id));
+    }
+    let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+    subscriptions.insert(subscription_id.to_string(), ActiveSubscription { stop, generation });
+    Ok(generation)
+}
+
+/// 只在登记的仍是这个订阅时移除，不影响之后使用同一 ID 的订阅
+fn unregister(subscription_id: &str, generation: u64) {
+    if let Ok(mut subscriptions) = get_subscriptions().lock()
+        && subscriptions.get(subscription_id).is_some_and(|active| active.generation == generation)
+    {
+        subscriptions.remove(subscription_id);
+    }
+}
+
+/// WebSocket 上的 GraphQL 子协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GraphQLWsProtocol {
+    /// graphql-ws 库的协议
+    #[serde(rename = "graphql-transport-ws")]
+    GraphqlTransportWs,
+    /// subscriptions-transport-ws（Apollo 旧版）的协议
+    #[serde(rename = "graphql-ws")]
+    SubscriptionsTransportWs,
+}
+
+impl GraphQLWsProtocol {
+    fn name(self) -> &'static str {
+        match self {
+            GraphQLWsProtocol::GraphqlTransportWs => "graphql-transport-ws",
+            GraphQLWsProtocol::SubscriptionsTransportWs => "graphql-ws",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "graphql-transport-ws" => Some(GraphQLWsProtocol::GraphqlTransportWs),
+            "graphql-ws" => Some(GraphQLWsProtocol::SubscriptionsTransportWs),
+            _ => None,
+        }
+    }
+
+    fn subscribe_type(self) -> &'static str {
+        match self {
+            GraphQLWsProtocol::GraphqlTransportWs => "subscribe",
+            GraphQLWsProtocol::SubscriptionsTransportWs => "start",
+        }
+    }
+
+    fn stop_type(self) -> &'static str {
+        match self {
+            GraphQLWsProtocol::GraphqlTransportWs => "complete",
+            GraphQLWsProtocol::SubscriptionsTransportWs => "stop",
+        }
+    }
+}
+
+/// GraphQL 订阅
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQLSubscriptionRequest {
+    /// 订阅 ID，用于关联事件和取消订阅
+    pub subscription_id: String,
+    /// ws:// 或 wss:// 地址，http(s):// 会自动转换
+    pub url: String,
+    pub query: String,
+    pub variables: Option<Json>,
+    pub operation_name: Option<String>,
+    /// 握手请求的请求头
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// connection_init 的 payload，通常用于传递认证信息
+    pub connection_params: Option<Json>,
+    /// 未指定时同时提供两种子协议，由服务端选择
+    pub protocol: Option<GraphQLWsProtocol>,
+    /// 未设置时使用全局配置
+    pub config: Option<ClientConfig>,
+}
+
+/// 订阅事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GraphQLEventKind {
+    /// 一条执行结果（含 data 和 errors）
+    Data,
+    /// 订阅出错，payload 为错误列表
+    Error,
+    /// 服务端结束了订阅
+    Complete,
+    /// 连接已关闭，之后不会再有事件
+    Closed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQLSubscriptionEvent {
+    pub subscription_id: String,
+    pub kind: GraphQLEventKind,
+    pub payload: Option<Json>,
+}
+
+fn emit_event(app: &AppHandle, subscription_id: &str, kind: GraphQLEventKind, payload: Option<Json>) {
+    let event = GraphQLSubscriptionEvent {
+        subscription_id: subscription_id.to_string(),
+        kind,
+        payload,
+    };
+    if let Err(e) = app.emit(GRAPHQL_SUBSCRIPTION_EVENT, event) {
+        log::warn!("Failed to emit {}: {}", GRAPHQL_SUBSCRIPTION_EVENT, e);
+    }
+}
+
+fn error_messages(errors: &Json) -> String {
+    match errors.as_array() {
+        Some(errors) => errors
+            .iter()
+            .map(|error| error.get("message").and_then(Json::as_str).unwrap_or("Unknown error").to_string())
+            .collect::<Vec<_>>()
+            .join("; "),
+        None => errors.to_string(),
+    }
+}
+
+/// 通过 send_request 执行内省查询（沿用全局客户端配置），结果按 URL 和请求头缓存；
+/// `refresh` 为 false 且已有缓存时直接返回缓存
+#[tauri::command]
+pub async fn introspect_graphql_schema(
+    app: AppHandle,
+    url: String,
+    headers: Option<HashMap<String, String>>,
+    refresh: Option<bool>,
+) -> Result<Json, String> {
+    let headers_key = headers_key(headers.as_ref());
+    if !refresh.unwrap_or(false) {
+        let cache = get_schema_cache().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+        if let Some(schema) = cache.get(&url).and_then(|schemas| schemas.get(&headers_key)) {
+            return Ok(schema.clone());
+        }
+    }
+
+    let mut request_headers = headers.unwrap_or_default();
+    if !request_headers.keys().any(|key| key.eq_ignore_ascii_case("content-type")) {
+        request_headers.insert("Content-Type".to_string(), "application/json".to_string());
+    }
+    if !request_headers.keys().any(|key| key.eq_ignore_ascii_case("accept")) {
+        request_headers.insert(
+            "Accept".to_string(),
+            "application/graphql-response+json, application/json".to_string(),
+        );
+    }
+    let body = json!({ "query": INTROSPECTION_QUERY, "operationName": "IntrospectionQuery" });
+    let config = HttpRequestConfig {
+        url: url.clone(),
+        method: "POST".to_string(),
+        headers: request_headers,
+        body: Some(body.to_string().into_bytes()),
+        ..Default::default()
+    };
+    let response = http_client::send_request(app, config).await?;
+
+    let text = match &response.text {
+        Some(text) => text.text.clone(),
+        None => String::from_utf8_lossy(&response.body).to_string(),
+    };
+    let result: Json = serde_json::from_str(&text).map_err(|_| {
+        format!("Introspection failed: HTTP {} {} (response is not JSON)", response.status, response.status_text)
+    })?;
+    let schema = match result.pointer("/data/__schema") {
+        Some(schema) if schema.is_object() => schema.clone(),
+        _ => {
+            return Err(match result.get("errors") {
+                Some(errors) => format!("Introspection failed: {}", error_messages(errors)),
+                None => format!("Introspection failed: HTTP {} {}", response.status, response.status_text),
+            });
+        }
+    };
+
+    let mut cache = get_schema_cache().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+    cache.entry(url).or_default().insert(headers_key, schema.clone());
+    Ok(schema)
+}
+
+/// 读取以相同请求头内省得到的缓存 schema，不发起请求
+#[tauri::command]
+pub fn get_cached_graphql_schema(url: String, headers: Option<HashMap<String, String>>) -> Result<Option<Json>, String> {
+    let cache = get_schema_cache().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+    Ok(cache
+        .get(&url)
+        .and_then(|schemas| schemas.get(&headers_key(headers.as_ref())))
+        .cloned())
+}
+
+/// 清除指定端点（未指定时为全部）缓存的 schema，包括以不同请求头得到的
+#[tauri::command]
+pub fn clear_graphql_schema_cache(url: Option<String>) -> Result<(), String> {
+    let mut cache = get_schema_cache().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+    match url {
+        Some(url) => {
+            cache.remove(&url);
+        }
+        None => cache.clear(),
+    }
+    Ok(())
+}
+
+async fn send_json(stream: &mut WsStream, message: Json) -> Result<(), String> {
+    stream
+        .send(Message::text(message.to_string()))
+        .await
+        .map_err(|e| format!("Failed to send message: {}", e))
+}
+
+/// 发送 connection_init 并等待 connection_ack
+async fn initialize(
+    stream: &mut WsStream,
+    protocol: GraphQLWsProtocol,
+    connection_params: Option<Json>,
+    timeout: Duration,
+) -> Result<(), String> {
+    let mut init = json!({ "type": "connection_init" });
+    if let Some(params) = connection_params {
+        init["payload"] = params;
+    }
+    send_json(stream, init).await?;
+
+    let wait_ack = async {
+        while let Some(frame) = stream.next().await {
+            let text = match frame.map_err(|e| format!("Connection failed: {}", e))? {
+                Message::Text(text) => text,
+                Message::Close(frame) => {
+                    let reason = frame.map(|frame| format!("{} {}", frame.code, frame.reason)).unwrap_or_default();
+                    return Err(format!("Server closed the connection: {}", reason.trim()));
+                }
+                _ => continue,
+            };
+            let message: Json = serde_json::from_str(&text).map_err(|e| format!("Invalid message from server: {}", e))?;
+            match message.get("type").and_then(Json::as_str) {
+                Some("connection_ack") => return Ok(()),
+                Some("connection_error") => {
+                    return Err(format!("Connection rejected: {}", message.get("payload").unwrap_or(&Json::Null)));
+                }
+                Some("ping") if protocol == GraphQLWsProtocol::GraphqlTransportWs => {
+                    send_json(stream, json!({ "type": "pong" })).await?;
+                }
+                _ => {}
+            }
+        }
+        Err("Server closed the connection before acknowledging".to_string())
+    };
+    tokio::time::timeout(timeout, wait_ack)
+        .await
+        .map_err(|_| "Timed out waiting for connection_ack".to_string())?
+}
+
+/// 处理一条服务端消息，返回订阅是否已结束
+async fn handle_message(
+    app: &AppHandle,
+    subscription_id: &str,
+    stream: &mut WsStream,
+    protocol: GraphQLWsProtocol,
+    text: &str,
+) -> Result<bool, String> {
+    let message: Json = serde_json::from_str(text).map_err(|e| format!("Invalid message from server: {}", e))?;
+    let payload = message.get("payload").cloned();
+    match (protocol, message.get("type").and_then(Json::as_str)) {
+        (GraphQLWsProtocol::GraphqlTransportWs, Some("next")) | (GraphQLWsProtocol::SubscriptionsTransportWs, Some("data")) => {
+            emit_event(app, subscription_id, GraphQLEventKind::Data, payload);
+            Ok(false)
+        }
+        (_, Some("error")) => {
+            emit_event(app, subscription_id, GraphQLEventKind::Error, payload);
+            // graphql-ws 中 error 之后订阅即结束
+            Ok(protocol == GraphQLWsProtocol::GraphqlTransportWs)
+        }
+        (_, Some("connection_error")) => {
+            emit_event(app, subscription_id, GraphQLEventKind::Error, payload);
+            Ok(true)
+        }
+        (_, Some("complete")) => {
+            emit_event(app, subscription_id, GraphQLEventKind::Complete, None);
+            Ok(true)
+        }
+        (GraphQLWsProtocol::GraphqlTransportWs, Some("ping")) => {
+            send_json(stream, json!({ "type": "pong" })).await?;
+            Ok(false)
+        }
+        // ka（保活）、pong 等消息无需处理
+        _ => Ok(false),
+    }
+}
+
+/// 接收订阅消息直到服务端结束、连接断开或取消订阅
+async fn run_subscription(
+    app: AppHandle,
+    subscription_id: String,
+    generation: u64,
+    mut stream: WsStream,
+    protocol: GraphQLWsProtocol,
+    mut stop: oneshot::Receiver<()>,
+) {
+    let mut close_reason = None;
+    loop {
+        tokio::select! {
+            _ = &mut stop => {
+                // 通知服务端停止，旧协议还需终止连接
+                let _ = send_json(&mut stream, json!({ "id": OPERATION_ID, "type": protocol.stop_type() })).await;
+                if protocol == GraphQLWsProtocol::SubscriptionsTransportWs {
+                    let _ = send_json(&mut stream, json!({ "type": "connection_terminate" })).await;
+                }
+                break;
+            }
+            frame = stream.next() => match frame {
+                Some(Ok(Message::Text(text))) => {
+                    match handle_message(&app, &subscription_id, &mut stream, protocol, &text).await {
+                        Ok(false) => {}
+                        Ok(true) => break,
+                        Err(e) => {
+                            close_reason = Some(e);
+                            break;
+                        }
+                    }
+                }
+                Some(Ok(Message::Close(frame))) => {
+                    close_reason = frame.map(|frame| format!("{} {}", frame.code, frame.reason).trim().to_string());
+                    break;
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    close_reason = Some(format!("Connection failed: {}", e));
+                    break;
+                }
+                None => break,
+            },
+        }
+    }
+
+    let _ = stream.close(None).await;
+    unregister(&subscription_id, generation);
+    emit_event(&app, &subscription_id, GraphQLEventKind::Closed, close_reason.map(Json::String));
+}
+
+/// 通过 WebSocket 发起订阅，握手完成后返回服务端选定的子协议，
+/// 之后的结果以 graphql-subscription 事件发出
+#[tauri::command]
+pub async fn graphql_subscribe(app: AppHandle, request: GraphQLSubscriptionRequest) -> Result<GraphQLWsProtocol, String> {
+    // 连接前先占用 ID，期间取消订阅时连接建立后立即停止
+    let (stop_sender, stop_receiver) = oneshot::channel();
+    let generation = register(&request.subscription_id, stop_sender)?;
+    let subscription_id = request.subscription_id.clone();
+    match start_subscription(request).await {
+        Ok((stream, protocol)) => {
+            tauri::async_runtime::spawn(run_subscription(app, subscription_id, generation, stream, protocol, stop_receiver));
+            Ok(protocol)
+        }
+        Err(e) => {
+            unregister(&subscription_id, generation);
+            Err(e)
+        }
+    }
+}
+
+/// 建立连接、完成初始化并发送订阅
+async fn start_subscription(request: GraphQLSubscriptionRequest) -> Result<(WsStream, GraphQLWsProtocol), String> {
+    let client_config = client_config_or_global(request.config)?;
+    let offered: Vec<&str> = match request.protocol {
+        Some(protocol) => vec![protocol.name()],
+        None => vec![
+            GraphQLWsProtocol::GraphqlTransportWs.name(),
+            GraphQLWsProtocol::SubscriptionsTransportWs.name(),
+        ],
+    };
+    let connection = ws::connect(&request.url, &request.headers, &offered, &client_config).await?;
+    // 服务端未返回子协议时按首选协议处理
+    let protocol = connection
+        .protocol
+        .as_deref()
+        .and_then(GraphQLWsProtocol::from_name)
+        .or(request.protocol)
+        .unwrap_or(GraphQLWsProtocol::GraphqlTransportWs);
+    let mut stream = connection.stream;
+
+    let ack_timeout = timeout_duration(client_config.timeout).unwrap_or(ACK_TIMEOUT);
+    initialize(&mut stream, protocol, request.connection_params, ack_timeout).await?;
+
+    let mut payload = json!({ "query": request.query });
+    if let Some(variables) = request.variables {
+        payload["variables"] = variables;
+    }
+    if let Some(operation_name) = request.operation_name {
+        payload["operationName"] = Json::String(operation_name);
+    }
+    send_json(
+        &mut stream,
+        json!({ "id": OPERATION_ID, "type": protocol.subscribe_type(), "payload": payload }),
+    )
+    .await?;
+
+    Ok((stream, protocol))
+}
+
+/// 取消订阅并关闭连接
+#[tauri::command]
+pub fn graphql_unsubscribe(subscription_id: String) -> Result<(), String> {
+    let stop = get_subscriptions()
+        .lock()
+        .map_err(|e| format!("Failed to acquire lock: {}", e))?
+        .remove(&subscription_id)
+        .ok_or_else(|| format!("Subscription {} is not active", subscription_id))?
+        .stop;
+    let _ = stop.send(());
+    Ok(())
+}
@@ -0,0 +1,495 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use hyper_util::client::legacy::connect::HttpConnector;
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, MethodDescriptor};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::metadata::{KeyAndValueRef, MetadataKey, MetadataMap, MetadataValue};
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Status};
+use tonic_reflection::pb::v1::server_reflection_client::ServerReflectionClient;
+use tonic_reflection::pb::v1::server_reflection_request::MessageRequest;
+use tonic_reflection::pb::v1::server_reflection_response::MessageResponse;
+use tonic_reflection::pb::v1::ServerReflectionRequest;
+
+use crate::http_client::{client_config_or_global, format_error_chain, timeout_duration, ClientConfig};
+use crate::{proto, tls};
+
+/// 流式调用中每收到一条响应消息发出的事件名
+pub const GRPC_MESSAGE_EVENT: &str = "grpc-message";
+
+/// gRPC 调用
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrpcRequest {
+    /// 请求 ID，设置后每条响应消息都会发出 grpc-message 事件
+    pub request_id: Option<String>,
+    /// 服务地址，如 `https://localhost:50051`
+    pub url: String,
+    /// 方法全名，如 `acme.v1.OrderService/GetOrder`
+    pub method: String,
+    /// JSON 格式的请求消息，非客户端流方法只能有一条
+    pub messages: Vec<String>,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    /// 通过服务端反射获取描述符，否则使用已注册的 .proto
+    #[serde(default)]
+    pub use_reflection: bool,
+    /// 未设置时使用全局配置
+    pub config: Option<ClientConfig>,
+}
+
+/// 流式响应中的一条消息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrpcMessage {
+    pub request_id: String,
+    pub index: usize,
+    pub message: String,
+}
+
+/// 调用各阶段耗时（毫秒）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrpcTiming {
+    /// 建立连接（含 TLS 握手）
+    pub connect: u64,
+    /// 从发出请求到收到响应头
+    pub wait: u64,
+    /// 总耗时，含反射查询
+    pub total: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrpcResponse {
+    /// gRPC 状态码，0 表示成功
+    pub status_code: i32,
+    /// 状态码名称，如 `NotFound`
+    pub status_name: String,
+    pub status_message: String,
+    /// 响应头中的元数据，二进制（-bin）值为 base64
+    pub metadata: HashMap<String, String>,
+    pub trailers: HashMap<String, String>,
+    /// JSON 格式的响应消息
+    pub messages: Vec<String>,
+    pub timing: GrpcTiming,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrpcMethod {
+    pub name: String,
+    /// 调用时使用的路径，如 `acme.v1.OrderService/GetOrder`
+    pub full_name: String,
+    pub input_type: String,
+    pub output_type: String,
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrpcService {
+    pub name: String,
+    pub methods: Vec<GrpcMethod>,
+}
+
+/// 按运行时描述符编解码消息
+#[derive(Clone)]
+struct DynamicCodec {
+    response: MessageDescriptor,
+}
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = DynamicEncoder;
+    type Decoder = DynamicDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        DynamicEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        DynamicDecoder(self.response.clone())
+    }
+}
+
+struct DynamicEncoder;
+
+impl Encoder for DynamicEncoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.encode(dst).map_err(|e| Status::internal(format!("Failed to encode message: {}", e)))
+    }
+}
+
+struct DynamicDecoder(MessageDescriptor);
+
+impl Decoder for DynamicDecoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        DynamicMessage::decode(self.0.clone(), src)
+            .map(Some)
+            .map_err(|e| Status::internal(format!("Failed to decode {}: {}", self.0.full_name(), e)))
+    }
+}
+
+/// 建立 HTTP/2 连接，https 地址使用与 send_request 相同的 TLS/CA 配置
+async fn connect(url: &str, client_config: &ClientConfig) -> Result<Channel, String> {
+    let mut endpoint = Endpoint::from_shared(url.to_string()).map_err(|e| format!("Invalid URL: {}", e))?;
+    if let Some(timeout) = client_config.connect_timeout.and_then(timeout_duration) {
+        endpoint = endpoint.connect_timeout(timeout);
+    }
+    if !client_config.user_agent.is_empty() {
+        endpoint = endpoint.user_agent(client_config.user_agent.as_str()).map_err(|e| format!("Invalid user agent: {}", e))?;
+    }
+
+    let mut tls_config = tls::build_tls_config(client_config)?;
+    // ALPN 由 enable_http2 设置为 h2，gRPC 只能使用 HTTP/2
+    tls_config.alpn_protocols.clear();
+
+    let mut http = HttpConnector::new();
+    http.enforce_http(false);
+    http.set_nodelay(true);
+    let connector = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_tls_config(tls_config)
+        .https_or_http()
+        .enable_http2()
+        .wrap_connector(http);
+
+    endpoint
+        .connect_with_connector(connector)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", url, format_error_chain(&e)))
+}
+
+/// 在截止时间之前等待 future 完成，超时返回 None
+async fn before_deadline<T>(deadline: Option<tokio::time::Instant>, future: impl Future<Output = T>) -> Option<T> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+/// 发送一条反射请求并返回响应
+async fn reflection_request(
+    client: &mut ServerReflectionClient<Channel>,
+    request: MessageRequest,
+) -> Result<MessageResponse, String> {
+    let request = ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(request),
+    };
+    let mut responses = client
+        .server_reflection_info(futures_util::stream::iter([request]))
+        .await
+        .map_err(|status| format!("Server reflection failed: {}", status.message()))?
+        .into_inner();
+    let response = responses
+        .message()
+        .await
+        .map_err(|status| format!("Server reflection failed: {}", status.message()))?
+        .and_then(|response| response.message_response)
+        .ok_or("Server reflection returned no response")?;
+    match response {
+        MessageResponse::ErrorResponse(error) => Err(format!("Server reflection failed: {}", error.error_message)),
+        response => Ok(response),
+    }
+}
+
+fn add_files(
+    files: &mut HashMap<String, prost_types::FileDescriptorProto>,
+    response: MessageResponse,
+) -> Result<(), String> {
+    let MessageResponse::FileDescriptorResponse(response) = response else {
+        return Err("Unexpected server reflection response".to_string());
+    };
+    for data in response.file_descriptor_proto {
+        let file = prost_types::FileDescriptorProto::decode(data.as_slice())
+            .map_err(|e| format!("Invalid descriptor from server: {}", e))?;
+        files.insert(file.name().to_string(), file);
+    }
+    Ok(())
+}
+
+/// 通过服务端反射获取定义这些符号的文件及其全部依赖
+async fn reflect_descriptors(channel: &Channel, symbols: &[String]) -> Result<DescriptorPool, String> {
+    let mut client = ServerReflectionClient::new(channel.clone());
+    let mut files: HashMap<String, prost_types::FileDescriptorProto> = HashMap::new();
+
+    for symbol in symbols {
+        let response = reflection_request(&mut client, MessageRequest::FileContainingSymbol(symbol.clone())).await?;
+        add_files(&mut files, response)?;
+    }
+    // 服务端可能省略依赖文件，逐个补齐
+    loop {
+        let missing: Vec<String> = files
+            .values()
+            .flat_map(|file| file.dependency.iter())
+            .filter(|dependency| !files.contains_key(*dependency))
+            .cloned()
+            .collect();
+        if missing.is_empty() {
+            break;
+        }
+        for name in missing {
+            let response = reflection_request(&mut client, MessageRequest::FileByFilename(name.clone())).await?;
+            add_files(&mut files, response)?;
+            if !files.contains_key(&name) {
+                return Err(format!("Server reflection did not return {}", name));
+            }
+        }
+    }
+
+    let mut pool = DescriptorPool::new();
+    pool.add_file_descriptor_protos(files.into_values())
+        .map_err(|e| format!("Invalid descriptor from server: {}", e))?;
+    Ok(pool)
+}
+
+/// 拆分方法名，支持 `pkg.Service/Method`、`/pkg.Service/Method` 和 `pkg.Service.Method`
+fn split_method(method: &str) -> Result<(&str, &str), String> {
+    let method = method.trim().trim_start_matches('/');
+    method
+        .rsplit_once('/')
+        .or_else(|| method.rsplit_once('.'))
+        .filter(|(service, name)| !service.is_empty() && !name.is_empty())
+        .ok_or_else(|| format!("Invalid method name: {}", method))
+}
+
+fn find_method(pool: &DescriptorPool, service: &str, name: &str) -> Result<MethodDescriptor, String> {
+    let service_descriptor =
+        pool.get_service_by_name(service).ok_or_else(|| format!("Unknown service: {}", service))?;
+    service_descriptor
+        .methods()
+        .find(|method| method.name() == name)
+        .ok_or_else(|| format!("Unknown method: {}/{}", service, name))
+}
+
+fn describe_service(service: &prost_reflect::ServiceDescriptor) -> GrpcService {
+    GrpcService {
+        name: service.full_name().to_string(),
+        methods: service
+            .methods()
+            .map(|method| GrpcMethod {
+                name: method.name().to_string(),
+                full_name: format!("{}/{}", service.full_name(), method.name()),
+                input_type: method.input().full_name().to_string(),
+                output_type: method.output().full_name().to_string(),
+                client_streaming: method.is_client_streaming(),
+                server_streaming: method.is_server_streaming(),
+            })
+            .collect(),
+    }
+}
+
+fn build_metadata(entries: &HashMap<String, String>) -> Result<MetadataMap, String> {
+    let mut metadata = MetadataMap::new();
+    for (key, value) in entries {
+        let key = key.trim().to_ascii_lowercase();
+        // -bin 结尾的键为二进制值，以 base64 传入
+        if key.ends_with("-bin") {
+            let key = MetadataKey::from_bytes(key.as_bytes()).map_err(|_| format!("Invalid metadata key: {}", key))?;
+            let data = BASE64
+                .decode(value.trim())
+                .map_err(|e| format!("Invalid base64 value for {}: {}", key, e))?;
+            metadata.append_bin(key, MetadataValue::from_bytes(&data));
+        } else {
+            let key = MetadataKey::from_bytes(key.as_bytes()).map_err(|_| format!("Invalid metadata key: {}", key))?;
+            let value = value.parse().map_err(|_| format!("Invalid metadata value for {}", key))?;
+            metadata.append(key, value);
+        }
+    }
+    Ok(metadata)
+}
+
+fn metadata_to_map(metadata: &MetadataMap) -> HashMap<String, String> {
+    let mut entries: HashMap<String, String> = HashMap::new();
+    for entry in metadata.iter() {
+        let (key, value) = match entry {
+            KeyAndValueRef::Ascii(key, value) => {
+                (key.to_string(), String::from_utf8_lossy(value.as_encoded_bytes()).to_string())
+            }
+            KeyAndValueRef::Binary(key, value) => match value.to_bytes() {
+                Ok(data) => (key.to_string(), BASE64.encode(data)),
+                Err(_) => (key.to_string(), String::from_utf8_lossy(value.as_encoded_bytes()).to_string()),
+            },
+        };
+        // 同名的多个值以逗号合并
+        entries
+            .entry(key)
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(&value);
+            })
+            .or_insert(value);
+    }
+    entries
+}
+
+fn millis_since(start: Instant) -> u64 {
+    start.elapsed().as_millis() as u64
+}
+
+/// 列出可调用的服务和方法：提供 url 且 use_reflection 时通过服务端反射查询，否则列出已注册的 .proto 中的服务
+#[tauri::command]
+pub async fn list_grpc_services(
+    url: Option<String>,
+    use_reflection: Option<bool>,
+    config: Option<ClientConfig>,
+) -> Result<Vec<GrpcService>, String> {
+    let pool = match (url, use_reflection.unwrap_or(false)) {
+        (Some(url), true) => {
+            let client_config = client_config_or_global(config)?;
+            let channel = connect(&url, &client_config).await?;
+            let mut client = ServerReflectionClient::new(channel.clone());
+            let MessageResponse::ListServicesResponse(response) =
+                reflection_request(&mut client, MessageRequest::ListServices(String::new())).await?
+            else {
+                return Err("Unexpected server reflection response".to_string());
+            };
+            let names: Vec<String> = response.service.into_iter().map(|service| service.name).collect();
+            reflect_descriptors(&channel, &names).await?
+        }
+        (None, true) => return Err("Server reflection requires a URL".to_string()),
+        _ => proto::descriptor_pool()?,
+    };
+
+    let mut services: Vec<GrpcService> = pool.services().map(|service| describe_service(&service)).collect();
+    services.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(services)
+}
+
+/// 发起 gRPC 调用（一元或流式）；非 OK 状态同样作为结果返回，连接或参数错误返回 Err
+#[tauri::command]
+pub async fn grpc_call(app: AppHandle, request: GrpcRequest) -> Result<GrpcResponse, String> {
+    let start = Instant::now();
+    let client_config = client_config_or_global(request.config)?;
+    let (service, name) = split_method(&request.method)?;
+    // 客户端同样按总超时截止：服务端可能忽略 grpc-timeout，流式响应也可能一直不结束
+    let deadline = timeout_duration(client_config.timeout).map(|timeout| tokio::time::Instant::now() + timeout);
+    let timed_out = || format!("Total timeout ({} ms) exceeded", client_config.timeout);
+    let deadline_exceeded = || Status::deadline_exceeded(timed_out());
+
+    let connect_start = Instant::now();
+    let channel = before_deadline(deadline, connect(&request.url, &client_config))
+        .await
+        .ok_or_else(timed_out)??;
+    let connect_time = millis_since(connect_start);
+
+    let pool = if request.use_reflection {
+        before_deadline(deadline, reflect_descriptors(&channel, &[service.to_string()]))
+            .await
+            .ok_or_else(timed_out)??
+    } else {
+        proto::descriptor_pool()?
+    };
+    let method = find_method(&pool, service, name)?;
+
+    if !method.is_client_streaming() && request.messages.len() != 1 {
+        return Err(format!(
+            "{}/{} expects exactly one request message, got {}",
+            service,
+            name,
+            request.messages.len()
+        ));
+    }
+    let messages = request
+        .messages
+        .iter()
+        .map(|json| proto::message_from_json(method.input(), json))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut grpc_request = tonic::Request::new(futures_util::stream::iter(messages));
+    *grpc_request.metadata_mut() = build_metadata(&request.metadata)?;
+    // 通过 grpc-timeout 把截止时间告知服务端
+    if let Some(timeout) = timeout_duration(client_config.timeout) {
+        grpc_request.set_timeout(timeout);
+    }
+
+    let path = format!("/{}/{}", service, name)
+        .parse()
+        .map_err(|e| format!("Invalid method name: {}", e))?;
+    let codec = DynamicCodec { response: method.output() };
+    let mut grpc = tonic::client::Grpc::new(channel);
+    before_deadline(deadline, grpc.ready())
+        .await
+        .ok_or_else(timed_out)?
+        .map_err(|e| format!("Failed to connect to {}: {}", request.url, e))?;
+
+    let wait_start = Instant::now();
+    let result = before_deadline(deadline, grpc.streaming(grpc_request, path, codec))
+        .await
+        .unwrap_or_else(|| Err(deadline_exceeded()));
+    let wait = millis_since(wait_start);
+
+    let mut response = GrpcResponse {
+        status_code: Code::Ok as i32,
+        status_name: format!("{:?}", Code::Ok),
+        status_message: String::new(),
+        metadata: HashMap::new(),
+        trailers: HashMap::new(),
+        messages: Vec::new(),
+        timing: GrpcTiming::default(),
+    };
+
+    let status = match result {
+        Ok(streaming) => {
+            let (metadata, mut stream, _) = streaming.into_parts();
+            response.metadata = metadata_to_map(&metadata);
+            // 超时前已收到的消息照常返回，状态为 DEADLINE_EXCEEDED
+            loop {
+                match before_deadline(deadline, stream.message()).await.unwrap_or_else(|| Err(deadline_exceeded())) {
+                    Ok(Some(message)) => {
+                        let json = proto::message_to_json(&message)?;
+                        if let Some(request_id) = &request.request_id {
+                            let event = GrpcMessage {
+                                request_id: request_id.clone(),
+                                index: response.messages.len(),
+                                message: json.clone(),
+                            };
+                            if let Err(e) = app.emit(GRPC_MESSAGE_EVENT, event) {
+                                log::warn!("Failed to emit {}: {}", GRPC_MESSAGE_EVENT, e);
+                            }
+                        }
+                        response.messages.push(json);
+                    }
+                    Ok(None) => {
+                        if let Some(Ok(Some(trailers))) = before_deadline(deadline, stream.trailers()).await {
+                            response.trailers = metadata_to_map(&trailers);
+                        }
+                        break None;
+                    }
+                    Err(status) => break Some(status),
+                }
+            }
+        }
+        Err(status) => Some(status),
+    };
+
+    if let Some(status) = status {
+        response.status_code = status.code() as i32;
+        response.status_name = format!("{:?}", status.code());
+        response.status_message = status.message().to_string();
+        // 只有尾部的响应（Trailers-Only）中元数据都在状态里
+        response.trailers = metadata_to_map(status.metadata());
+    }
+
+    response.timing = GrpcTiming {
+        connect: connect_time,
+        wait,
+        total: millis_since(start),
+    };
+    Ok(response)
+}
@@ -4,6 +4,20 @@ use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
 use reqwest::cookie::Jar;
 use std::time::{SystemTime, UNIX_EPOCH};
+use crate::body::{BodySource, PreparedBody};
+use crate::body_cache;
+use crate::charset::{self, DecodedText};
+use crate::codec::{self, BinaryFormat};
+use crate::compression::{ContentCoding, Decoder, EncodingOptions};
+use crate::dns::{OverrideResolver, ResolveOverride};
+use crate::limit::{self, LimitedBody, ReadBodyError, ResponseLimit};
+use crate::download::{self, DownloadOptions, DownloadResult};
+use crate::proto;
+use crate::progress::{self, DownloadReporter};
+use crate::sniff::{self, ContentInfo};
+use crate::retry::{RetryAttempt, RetryPolicy, RetryableFailure};
+use crate::throttle::{DownloadPacer, NetworkConditions, UploadPacer};
+use crate::tls::{self, CertificateInfo, TlsDetails, TlsOptions};
 
 // Cookie 持久化结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,26 +59,102 @@ impl Default for ProxyConfig {
     }
 }
 
+/// HTTP 协议版本偏好
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HttpVersionPreference {
+    /// 通过 ALPN 协商 HTTP/2 或 HTTP/1.1
+    #[default]
+    Auto,
+    /// 只使用 HTTP/1.1
+    Http1,
+    /// 通过 ALPN 协商 HTTP/2，服务端不支持时请求失败
+    Http2,
+    /// 直接以 HTTP/2 通信（明文时即 h2c）
+    Http2PriorKnowledge,
+    /// 实验性的 HTTP/3（QUIC），需要启用 http3 特性编译
+    Http3,
+}
+
+impl HttpVersionPreference {
+    /// TLS 握手时通过 ALPN 提供的协议
+    pub fn alpn_protocols(&self) -> Vec<Vec<u8>> {
+        match self {
+            HttpVersionPreference::Auto => vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+            HttpVersionPreference::Http1 => vec![b"http/1.1".to_vec()],
+            HttpVersionPreference::Http2 | HttpVersionPreference::Http2PriorKnowledge => vec![b"h2".to_vec()],
+            HttpVersionPreference::Http3 => vec![b"h3".to_vec()],
+        }
+    }
+
+    /// 请求上需要指定的版本
+    fn request_version(&self) -> Option<reqwest::Version> {
+        match self {
+            HttpVersionPreference::Auto | HttpVersionPreference::Http1 => None,
+            HttpVersionPreference::Http2 | HttpVersionPreference::Http2PriorKnowledge => Some(reqwest::Version::HTTP_2),
+            HttpVersionPreference::Http3 => Some(reqwest::Version::HTTP_3),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ClientConfig {
+    /// 总超时（毫秒），覆盖从发送请求到读完响应体（下载到文件时只到收到响应头），0 表示不限制
     pub timeout: u64,
+    /// 建立连接（含 TLS 握手）的超时（毫秒）
+    #[serde(default)]
+    pub connect_timeout: Option<u64>,
+    /// 读超时（毫秒）：等待响应或两次读取之间的最长间隔
+    #[serde(default)]
+    pub read_timeout: Option<u64>,
+    /// 空闲连接在连接池中保留的时间（毫秒）
+    #[serde(default)]
+    pub idle_timeout: Option<u64>,
     pub verify_ssl: bool,
     pub follow_redirects: bool,
     pub user_agent: String,
     pub ca_cert_paths: Vec<String>,
     pub proxy: ProxyConfig,
+    #[serde(default)]
+    pub resolve_overrides: Vec<ResolveOverride>,
+    #[serde(default)]
+    pub tls: TlsOptions,
+    #[serde(default)]
+    pub http_version: HttpVersionPreference,
+    /// Unix 域套接字路径（Windows 上为 `\\.\pipe\name` 形式的命名管道）
+    #[serde(default)]
+    pub socket_path: Option<String>,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    #[serde(default)]
+    pub network: NetworkConditions,
+    #[serde(default)]
+    pub response_limit: ResponseLimit,
+    #[serde(default)]
+    pub encoding: EncodingOptions,
 }
 
 impl Default for ClientConfig {
     fn default() -> Self {
         ClientConfig {
             timeout: 30000,
+            connect_timeout: None,
+            read_timeout: None,
+            idle_timeout: None,
             verify_ssl: true,
             follow_redirects: true,
             user_agent: "Teapot/1.0".to_string(),
             ca_cert_paths: Vec::new(),
             proxy: ProxyConfig::default(),
+            resolve_overrides: Vec::new(),
+            tls: TlsOptions::default(),
+            http_version: HttpVersionPreference::default(),
+            socket_path: None,
+            retry: RetryPolicy::default(),
+            network: NetworkConditions::default(),
+            response_limit: ResponseLimit::default(),
+            encoding: EncodingOptions::default(),
         }
     }
 }
@@ -73,11 +163,22 @@ impl ClientConfig {
     fn from_request(config: &HttpRequestConfig) -> Self {
         ClientConfig {
             timeout: config.timeout.unwrap_or(30000),
+            connect_timeout: config.connect_timeout,
+            read_timeout: config.read_timeout,
+            idle_timeout: config.idle_timeout,
             verify_ssl: config.verify_ssl.unwrap_or(true),
             follow_redirects: config.follow_redirects.unwrap_or(true),
             user_agent: config.user_agent.clone().unwrap_or_else(|| "Teapot/1.0".to_string()),
             ca_cert_paths: config.ca_cert_paths.clone().unwrap_or_default(),
             proxy: config.proxy.clone().unwrap_or_default(),
+            resolve_overrides: config.resolve_overrides.clone().unwrap_or_default(),
+            tls: config.tls.clone().unwrap_or_default(),
+            http_version: config.http_version.unwrap_or_default(),
+            socket_path: config.socket_path.clone(),
+            retry: config.retry.clone().unwrap_or_default(),
+            network: config.network.clone().unwrap_or_default(),
+            response_limit: config.response_limit.clone().unwrap_or_default(),
+            encoding: config.encoding.clone().unwrap_or_default(),
         }
     }
 
@@ -86,7 +187,9 @@ impl ClientConfig {
         use std::hash::{Hash, Hasher};
 
         let mut hasher = DefaultHasher::new();
-        self.timeout.hash(&mut hasher);
+        self.connect_timeout.hash(&mut hasher);
+        self.read_timeout.hash(&mut hasher);
+        self.idle_timeout.hash(&mut hasher);
         self.verify_ssl.hash(&mut hasher);
         self.follow_redirects.hash(&mut hasher);
         self.user_agent.hash(&mut hasher);
@@ -97,11 +200,15 @@ impl ClientConfig {
         self.proxy.protocol.hash(&mut hasher);
         self.proxy.username.hash(&mut hasher);
         self.proxy.password.hash(&mut hasher);
+        self.resolve_overrides.hash(&mut hasher);
+        self.tls.hash(&mut hasher);
+        self.http_version.hash(&mut hasher);
+        self.socket_path.hash(&mut hasher);
         format!("{:x}", hasher.finish())
     }
 }
 
-type CachedClient = (String, Arc<reqwest::Client>);
+type CachedClient = (String, Arc<reqwest::Client>, tls::HandshakeRecorder);
 
 static CLIENT_CACHE: OnceLock<Arc<Mutex<Option<CachedClient>>>> = OnceLock::new();
 static COOKIE_JAR: OnceLock<Arc<Mutex<Option<Arc<Jar>>>>> = OnceLock::new();
@@ -121,10 +228,85 @@ fn get_cookie_jar() -> Arc<Jar> {
     jar_guard.as_ref().unwrap().clone()
 }
 
-fn get_global_config() -> Arc<Mutex<ClientConfig>> {
+pub(crate) fn get_global_config() -> Arc<Mutex<ClientConfig>> {
     GLOBAL_CONFIG.get_or_init(|| Arc::new(Mutex::new(ClientConfig::default()))).clone()
 }
 
+/// 调用方未提供配置时使用全局配置
+pub(crate) fn client_config_or_global(config: Option<ClientConfig>) -> Result<ClientConfig, String> {
+    match config {
+        Some(config) => Ok(config),
+        None => {
+            let global_config = get_global_config();
+            let config_guard = global_config.lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+            Ok(config_guard.clone())
+        }
+    }
+}
+
+/// 展开错误链，便于看到证书校验失败等底层原因
+pub(crate) fn format_error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        let cause_message = cause.to_string();
+        if !message.contains(&cause_message) {
+            message.push_str(": ");
+            message.push_str(&cause_message);
+        }
+        source = cause.source();
+    }
+    message
+}
+
+fn format_http_version(version: reqwest::Version) -> String {
+    match version {
+        reqwest::Version::HTTP_09 => "HTTP/0.9",
+        reqwest::Version::HTTP_10 => "HTTP/1.0",
+        reqwest::Version::HTTP_11 => "HTTP/1.1",
+        reqwest::Version::HTTP_2 => "HTTP/2",
+        reqwest::Version::HTTP_3 => "HTTP/3",
+        _ => "Unknown",
+    }
+    .to_string()
+}
+
+/// 毫秒转为超时时间，0 表示不限制
+pub(crate) fn timeout_duration(millis: u64) -> Option<Duration> {
+    (millis > 0).then(|| Duration::from_millis(millis))
+}
+
+/// 请求错误描述，超时时指明是哪一个超时触发
+fn describe_request_error(error: &reqwest::Error, client_config: &ClientConfig) -> String {
+    let message = format_error_chain(error);
+    if !error.is_timeout() {
+        return message;
+    }
+
+    if error.is_connect() {
+        if let Some(millis) = client_config.connect_timeout {
+            return format!("Connect timeout ({} ms) exceeded: {}", millis, message);
+        }
+    } else if let Some(millis) = client_config.read_timeout {
+        return format!("Read timeout ({} ms) exceeded: {}", millis, message);
+    }
+    message
+}
+
+/// 在总超时的截止时间之前等待 future 完成
+async fn within_deadline<T>(
+    deadline: Option<tokio::time::Instant>,
+    timeout: u64,
+    future: impl std::future::Future<Output = T>,
+) -> Result<T, String> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, future)
+            .await
+            .map_err(|_| format!("Total timeout ({} ms) exceeded", timeout)),
+        None => Ok(future.await),
+    }
+}
+
 fn get_cookie_store_path() -> Option<String> {
     COOKIE_STORE_PATH.get().and_then(|p| p.clone())
 }
@@ -288,20 +470,77 @@ pub fn init_cookie_storage() -> Result<(), String> {
     Ok(())
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HttpRequestConfig {
+    /// 请求 ID，设置后会发出与之关联的进度事件
+    pub request_id: Option<String>,
     pub url: String,
     pub method: String,
     pub headers: HashMap<String, String>,
     pub body: Option<Vec<u8>>,
+    /// 结构化的请求体（文件、表单等），设置后忽略 body
+    pub body_source: Option<BodySource>,
     pub timeout: Option<u64>,
+    pub connect_timeout: Option<u64>,
+    pub read_timeout: Option<u64>,
+    pub idle_timeout: Option<u64>,
     pub verify_ssl: Option<bool>,
     pub follow_redirects: Option<bool>,
     pub user_agent: Option<String>,
     pub ca_cert_paths: Option<Vec<String>>,
     pub use_global_config: Option<bool>,
     pub proxy: Option<ProxyConfig>,
+    pub resolve_overrides: Option<Vec<ResolveOverride>>,
+    pub include_certificates: Option<bool>,
+    pub tls: Option<TlsOptions>,
+    pub http_version: Option<HttpVersionPreference>,
+    /// 通过 Unix 域套接字发送请求，URL 可只写路径（如 `/containers/json`）
+    pub socket_path: Option<String>,
+    pub retry: Option<RetryPolicy>,
+    pub network: Option<NetworkConditions>,
+    pub response_limit: Option<ResponseLimit>,
+    /// Accept-Encoding 协商及是否返回原始响应体
+    pub encoding: Option<EncodingOptions>,
+    /// 压缩请求体并设置 Content-Encoding
+    pub compression: Option<ContentCoding>,
+    /// 手动指定响应体的字符集，覆盖自动检测
+    pub charset: Option<String>,
+    /// 按已注册的 .proto 解码响应体的消息类型，未指定时使用 Content-Type 中的 messageType 参数
+    pub response_message_type: Option<String>,
+    /// 把响应体直接写入文件，响应中只返回开头的预览
+    pub download: Option<DownloadOptions>,
+    /// 响应体保存在后端缓存中，通过 fetch_response_body 以二进制获取，body 字段为空
+    pub binary_body: Option<bool>,
+}
+
+/// 实际发送的请求体大小
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestBodySize {
+    /// 压缩前的字节数
+    pub original_size: u64,
+    /// 发送的字节数（压缩后）
+    pub sent_size: u64,
+    pub content_encoding: Option<String>,
+}
+
+/// 请求各阶段耗时（毫秒）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseTiming {
+    /// 网络模拟附加的延迟（所有尝试累计）
+    pub simulated_latency: u64,
+    /// 上行限速附加的等待（所有尝试累计）
+    pub upload_throttle: u64,
+    /// 最后一次尝试从发出请求到收到响应头
+    pub wait: u64,
+    /// 读取响应体，含下行限速的等待
+    pub download: u64,
+    /// 下行限速附加的等待
+    pub download_throttle: u64,
+    /// 总耗时，与 duration 相同
+    pub total: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -311,18 +550,77 @@ pub struct HttpResponse {
     pub status_text: String,
     pub headers: HashMap<String, String>,
     pub body: Vec<u8>,
+    /// 缓存的响应体 ID（binary_body 模式或有 spill_path 时），release_response_body 释放时一并删除临时文件
+    pub body_id: Option<String>,
     pub size: usize,
+    /// 响应头中的 Content-Length
+    pub content_length: Option<u64>,
+    /// 响应体超过大小限制被截断
+    pub truncated: bool,
+    /// 超过大小限制时完整响应体所在的临时文件，释放 body_id 或被缓存淘汰时删除
+    pub spill_path: Option<String>,
+    /// 响应体解码前的字节数
+    pub encoded_size: u64,
+    /// 响应体解码后的字节数
+    pub decoded_size: u64,
+    /// 解码前的原始响应体（encoding.includeRawBody），binary_body 模式下为 None
+    pub raw_body: Option<Vec<u8>>,
+    /// 缓存的原始响应体 ID（binary_body 模式下的 encoding.includeRawBody）
+    pub raw_body_id: Option<String>,
+    /// 原始响应体不完整（超出大小限制被截断）
+    pub raw_body_truncated: bool,
+    /// 按检测到的字符集解码的文本，非文本内容或 binary_body 模式下为 None
+    pub text: Option<DecodedText>,
+    /// binary_body 模式下文本内容使用的编码（WHATWG 名称），由前端按此解码 fetch_response_body 取回的响应体
+    pub text_encoding: Option<String>,
+    /// 响应体的实际类型和建议的查看方式，Content-Type 缺失时按内容推断
+    pub content_info: ContentInfo,
+    /// CBOR / MessagePack / protobuf 响应体转换成的格式化 JSON
+    pub decoded_body: Option<String>,
+    pub request_body: Option<RequestBodySize>,
     pub duration: u64,
+    pub remote_addr: Option<String>,
+    pub http_version: String,
+    pub connection_reused: Option<bool>,
+    pub tls: Option<TlsDetails>,
+    pub certificates: Option<Vec<CertificateInfo>>,
+    /// 每一次尝试的结果（含最终成功的一次）
+    pub attempts: Vec<RetryAttempt>,
+    pub timing: ResponseTiming,
+    pub download: Option<DownloadResult>,
+}
+
+/// 把 CBOR、MessagePack 和 protobuf 响应体转换为 JSON，显式指定的消息类型优先
+fn decode_structured_body(
+    body: &[u8],
+    content_type: Option<&str>,
+    content_info: &ContentInfo,
+    message_type: Option<&str>,
+) -> Option<String> {
+    let decoded = if let Some(message_type) = message_type {
+        proto::decode_to_json(body, message_type)
+    } else if let Some(format) = BinaryFormat::from_content_type(&content_info.mime_type) {
+        codec::decode_to_json(body, format)
+    } else if proto::is_protobuf_content_type(&content_info.mime_type) {
+        let message_type = content_type.and_then(proto::content_type_message)?;
+        proto::decode_to_json(body, &message_type)
+    } else {
+        return None;
+    };
+    decoded.inspect_err(|e| log::warn!("{}", e)).ok()
 }
 
 #[tauri::command]
-pub async fn send_request(config: HttpRequestConfig) -> Result<HttpResponse, String> {
-    use reqwest::Certificate;
+pub async fn send_request(app: tauri::AppHandle, config: HttpRequestConfig) -> Result<HttpResponse, String> {
     use reqwest::Client;
     use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+    use hyper_util::client::legacy::connect::HttpInfo;
+
+    // 先校验手动指定的字符集，避免请求发出后才报错
+    let charset_override = config.charset.as_deref().map(charset::encoding_for_label).transpose()?;
 
     // 根据配置决定使用全局配置还是请求中的配置
-    let client_config = if !config.use_global_config.unwrap_or(true) {
+    let mut client_config = if !config.use_global_config.unwrap_or(true) {
         // use_global_config 为 false 时使用请求中的配置
         ClientConfig::from_request(&config)
     } else {
@@ -332,21 +630,95 @@ pub async fn send_request(config: HttpRequestConfig) -> Result<HttpResponse, Str
         config_guard.clone()
     };
 
-    let config_hash = client_config.hash();
+    // 请求中指定的超时、协议版本等优先于全局配置
+    if let Some(timeout) = config.timeout {
+        client_config.timeout = timeout;
+    }
+    if let Some(connect_timeout) = config.connect_timeout {
+        client_config.connect_timeout = Some(connect_timeout);
+    }
+    if let Some(read_timeout) = config.read_timeout {
+        client_config.read_timeout = Some(read_timeout);
+    }
+    if let Some(idle_timeout) = config.idle_timeout {
+        client_config.idle_timeout = Some(idle_timeout);
+    }
+    if let Some(http_version) = config.http_version {
+        client_config.http_version = http_version;
+    }
+    if let Some(retry) = &config.retry {
+        client_config.retry = retry.clone();
+    }
+    if let Some(network) = &config.network {
+        client_config.network = network.clone();
+    }
+    if let Some(response_limit) = &config.response_limit {
+        client_config.response_limit = response_limit.clone();
+    }
+    if let Some(encoding) = &config.encoding {
+        client_config.encoding = encoding.clone();
+    }
+    // 本地套接字属于请求本身，不受全局配置影响
+    client_config.socket_path = config
+        .socket_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(|path| path.to_string());
+
+    // SNI 覆盖：URL 主机替换为 SNI 名称，DNS 通过别名仍解析到原主机，Host 头保持不变
+    // 走本地套接字时 URL 可以只写路径，主机名仅用于 Host 头
+    let url = match &client_config.socket_path {
+        Some(_) if config.url.starts_with('/') => format!("http://localhost{}", config.url),
+        _ => config.url.clone(),
+    };
+    let mut request_url = reqwest::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
+    let mut host_override = None;
+    let mut sni_alias = None;
+    if let Some(server_name) = tls::sni_override(&client_config.tls)
+        && request_url.scheme() == "https"
+        && let Some(original_host) = request_url.host_str().map(|host| host.to_string())
+        && !original_host.eq_ignore_ascii_case(server_name)
+    {
+        sni_alias = Some((server_name.to_string(), original_host.clone()));
+        host_override = Some(match request_url.port() {
+            Some(port) => format!("{}:{}", original_host, port),
+            None => original_host,
+        });
+        request_url
+            .set_host(Some(server_name))
+            .map_err(|e| format!("Invalid server name {}: {}", server_name, e))?;
+    }
+
+    // 别名只属于这个 client 的解析器，原主机不同时不能复用
+    let config_hash = match &sni_alias {
+        Some((_, original_host)) => format!("{}@{}", client_config.hash(), original_host),
+        None => client_config.hash(),
+    };
 
     // 尝试从缓存获取 client
-    let client = {
+    let (client, recorder) = {
         let mut cache = get_client_cache().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
         match &*cache {
-            Some((cached_hash, cached_client)) if cached_hash == &config_hash => {
+            Some((cached_hash, cached_client, cached_recorder)) if cached_hash == &config_hash => {
                 // 配置未变化，使用缓存的 client
-                cached_client.clone()
+                (cached_client.clone(), cached_recorder.clone())
             }
             _ => {
                 // 配置变化或无缓存，重建 client
-                let timeout = Duration::from_millis(client_config.timeout);
+                // 总超时在发送请求时单独计算，以便区分是哪一个超时触发
+                let mut client_builder = Client::builder();
 
-                let mut client_builder = Client::builder().timeout(timeout);
+                // Configure timeouts
+                if let Some(timeout) = client_config.connect_timeout.and_then(timeout_duration) {
+                    client_builder = client_builder.connect_timeout(timeout);
+                }
+                if let Some(timeout) = client_config.read_timeout.and_then(timeout_duration) {
+                    client_builder = client_builder.read_timeout(timeout);
+                }
+                if let Some(millis) = client_config.idle_timeout {
+                    client_builder = client_builder.pool_idle_timeout(timeout_duration(millis));
+                }
 
                 // Configure redirect policy
                 if client_config.follow_redirects {
@@ -355,27 +727,35 @@ pub async fn send_request(config: HttpRequestConfig) -> Result<HttpResponse, Str
                     client_builder = client_builder.redirect(reqwest::redirect::Policy::none());
                 }
 
-                // Configure SSL verification
-                if !client_config.verify_ssl {
-                    client_builder = client_builder.danger_accept_invalid_certs(true);
+                // Configure TLS: SSL verification, custom CA certificates and handshake recording
+                let tls_config = tls::build_tls_config(&client_config)?;
+                let recorder = tls::HandshakeRecorder::new(client_config.http_version.alpn_protocols());
+                client_builder = client_builder
+                    .tls_backend_preconfigured(tls_config)
+                    .connector_layer(recorder.clone());
+
+                // Configure HTTP version
+                match client_config.http_version {
+                    HttpVersionPreference::Auto | HttpVersionPreference::Http2 => {}
+                    HttpVersionPreference::Http1 => {
+                        client_builder = client_builder.http1_only();
+                    }
+                    HttpVersionPreference::Http2PriorKnowledge => {
+                        client_builder = client_builder.http2_prior_knowledge();
+                    }
+                    #[cfg(feature = "http3")]
+                    HttpVersionPreference::Http3 => {
+                        client_builder = client_builder.http3_prior_knowledge();
+                    }
+                    #[cfg(not(feature = "http3"))]
+                    HttpVersionPreference::Http3 => {
+                        return Err("HTTP/3 support is not enabled in this build".to_string());
+                    }
                 }
 
                 // Set cookie jar - 所有请求共享 cookie
                 client_builder = client_builder.cookie_provider(get_cookie_jar());
 
-                // Support custom CA certificates loading
-                if !client_config.ca_cert_paths.is_empty() {
-                    let mut certificates = Vec::new();
-                    for ca_cert_path in &client_config.ca_cert_paths {
-                        let ca_cert_pem = std::fs::read_to_string(ca_cert_path)
-                            .map_err(|e| format!("Failed to read CA certificate from {}: {}", ca_cert_path, e))?;
-                        let ca_cert = Certificate::from_pem(ca_cert_pem.as_bytes())
-                            .map_err(|e| format!("Failed to parse CA certificate from {}: {}", ca_cert_path, e))?;
-                        certificates.push(ca_cert);
-                    }
-                    client_builder = client_builder.tls_certs_merge(certificates);
-                }
-
                 // Configure proxy
                 if client_config.proxy.enabled {
                     use reqwest::Proxy;
@@ -396,32 +776,61 @@ pub async fn send_request(config: HttpRequestConfig) -> Result<HttpResponse, Str
                     client_builder = client_builder.proxy(proxy);
                 }
 
+                // Configure local socket transport (proxy and DNS settings do not apply)
+                if let Some(socket_path) = &client_config.socket_path {
+                    #[cfg(unix)]
+                    {
+                        client_builder = client_builder.unix_socket(socket_path.clone());
+                    }
+                    #[cfg(windows)]
+                    {
+                        client_builder = client_builder.windows_named_pipe(socket_path.clone());
+                    }
+                    #[cfg(not(any(unix, windows)))]
+                    {
+                        return Err(format!("Local socket transport is not supported on this platform: {}", socket_path));
+                    }
+                }
+
+                // Configure DNS overrides (SNI overrides resolve through host aliases)
+                if !client_config.resolve_overrides.is_empty() || sni_alias.is_some() {
+                    let mut resolver = OverrideResolver::new(&client_config.resolve_overrides)?;
+                    if let Some((server_name, original_host)) = &sni_alias {
+                        resolver = resolver.with_alias(server_name, original_host);
+                    }
+                    client_builder = client_builder.dns_resolver(Arc::new(resolver));
+                }
+
                 let new_client = client_builder
                     .build()
                     .map_err(|e| format!("Failed to build client: {}", e))?;
 
                 let arc_client = Arc::new(new_client);
-                *cache = Some((config_hash.clone(), arc_client.clone()));
-                arc_client
+                *cache = Some((config_hash.clone(), arc_client.clone(), recorder.clone()));
+                (arc_client, recorder)
             }
         }
     };
 
     // Build request
     let mut request = match config.method.to_uppercase().as_str() {
-        "GET" => client.get(&config.url),
-        "POST" => client.post(&config.url),
-        "PUT" => client.put(&config.url),
-        "DELETE" => client.delete(&config.url),
-        "PATCH" => client.patch(&config.url),
-        "HEAD" => client.head(&config.url),
+        "GET" => client.get(request_url),
+        "POST" => client.post(request_url),
+        "PUT" => client.put(request_url),
+        "DELETE" => client.delete(request_url),
+        "PATCH" => client.patch(request_url),
+        "HEAD" => client.head(request_url),
         _ => {
             let method = reqwest::Method::from_bytes(config.method.as_bytes())
                 .map_err(|e| format!("Invalid HTTP method: {}", e))?;
-            client.request(method, &config.url)
+            client.request(method, request_url)
         }
     };
 
+    if let Some(version) = client_config.http_version.request_version() {
+        request = request.version(version);
+    }
+
     // Add headers (always present, empty map if no headers)
     let mut header_map = HeaderMap::new();
     for (key, value) in &config.headers {
@@ -440,25 +849,201 @@ pub async fn send_request(config: HttpRequestConfig) -> Result<HttpResponse, Str
         header_map.insert(ua_header, ua_value);
     }
 
+    // 覆盖 SNI 时保留原始 Host（用户显式设置的 Host 优先）
+    if let Some(host) = host_override
+        && !header_map.contains_key(reqwest::header::HOST)
+        && let Ok(host_value) = HeaderValue::from_str(&host)
+    {
+        header_map.insert(reqwest::header::HOST, host_value);
+    }
+
+    // 请求体：body_source 优先于内存中的 body
+    let body = match &config.body_source {
+        Some(source) => Some(source.prepare().await?),
+        None => config.body.clone().map(PreparedBody::bytes),
+    };
+
+    // 发送前压缩请求体，Content-Length 为压缩后的长度
+    let compression = config.compression.filter(|_| body.is_some());
+    let body = match (body, compression) {
+        (Some(body), Some(coding)) => Some(body.compress(coding).await?),
+        (body, _) => body,
+    };
+    if let Some(coding) = compression {
+        header_map.insert(reqwest::header::CONTENT_ENCODING, HeaderValue::from_static(coding.token()));
+        header_map.remove(reqwest::header::CONTENT_LENGTH);
+    }
+
+    // 按请求体类型设置 Content-Type（multipart 的边界必须与请求体一致）
+    if let Some((content_type, replace)) = body.as_ref().and_then(|body| body.content_type())
+        && (replace || !header_map.contains_key(reqwest::header::CONTENT_TYPE))
+        && let Ok(content_type) = HeaderValue::from_str(content_type)
+    {
+        header_map.insert(reqwest::header::CONTENT_TYPE, content_type);
+    }
+
+    // 分块上传时显式设置 Content-Length，避免改用 chunked 编码
+    if let Some(content_length) = body.as_ref().map(PreparedBody::len)
+        && !header_map.contains_key(reqwest::header::CONTENT_LENGTH)
+    {
+        header_map.insert(reqwest::header::CONTENT_LENGTH, HeaderValue::from(content_length));
+    }
+
+    // 续传下载：请求已有内容之后的部分（用户显式设置的 Range 优先）
+    let resume = match &config.download {
+        Some(download) if !header_map.contains_key(reqwest::header::RANGE) => {
+            download::resume_request(download, &config.url).await
+        }
+        _ => None,
+    };
+    if let Some(resume) = &resume
+        && let Ok(range) = HeaderValue::from_str(&format!("bytes={}-", resume.offset))
+        && let Ok(if_range) = HeaderValue::from_str(&resume.if_range)
+    {
+        header_map.insert(reqwest::header::RANGE, range);
+        header_map.insert(reqwest::header::IF_RANGE, if_range);
+    }
+
+    // 协商响应压缩（用户显式设置的 Accept-Encoding 优先）；下载按原样写入文件，不做协商
+    if config.download.is_none()
+        && !header_map.contains_key(reqwest::header::ACCEPT_ENCODING)
+        && let Ok(accept_encoding) = HeaderValue::from_str(&client_config.encoding.accept_encoding())
+    {
+        header_map.insert(reqwest::header::ACCEPT_ENCODING, accept_encoding);
+    }
+
     if !header_map.is_empty() {
         request = request.headers(header_map);
     }
 
-    // Add body
-    if let Some(body) = &config.body {
-        request = request.body(body.clone());
-    }
+    // 请求体在每次尝试时重新附加，带请求 ID 或上行限速时分块发送（报告上传进度、控制发送速度）
 
-    // Execute request with timing
+    // Execute request with timing and retries
     let start = std::time::Instant::now();
+    let network = client_config.network.profile();
+    let upload_pacer = UploadPacer::new(&network);
+    let mut timing = ResponseTiming::default();
+    let mut retry = client_config.retry.clone();
+    if !retry.applies_to(&config.method) {
+        retry.max_attempts = 1;
+    }
+    // 总超时覆盖所有尝试、重试前的等待和网络模拟的延迟
+    let deadline = timeout_duration(client_config.timeout).map(|timeout| tokio::time::Instant::now() + timeout);
+    let mut request = Some(request);
+    let mut attempts = Vec::new();
+    let mut attempt = 1;
+    let response = loop {
+        // 无法复制的请求体只能发送一次
+        let attempt_request = match request.as_ref().and_then(|request| request.try_clone()) {
+            Some(cloned) => cloned,
+            None => {
+                retry.max_attempts = attempt;
+                request.take().ok_or("Request body cannot be sent again")?
+            }
+        };
+        let attempt_request = match &body {
+            Some(body) if config.request_id.is_some() || upload_pacer.is_limited() => {
+                let mut chunks = body.stream().await?;
+                if let Some(request_id) = &config.request_id {
+                    chunks = progress::upload_body(&app, request_id, body.len(), chunks);
+                }
+                attempt_request.body(reqwest::Body::wrap_stream(upload_pacer.pace(chunks)))
+            }
+            Some(body) => attempt_request.body(body.request_body().await?),
+            None => attempt_request,
+        };
+
+        let attempt_start = std::time::Instant::now();
 
-    let response = request
-        .send()
-        .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+        // 网络模拟：附加延迟计入本次尝试，上行限速在发送请求体时进行
+        let simulated = if network.latency.is_zero() {
+            Ok(())
+        } else {
+            within_deadline(deadline, client_config.timeout, tokio::time::sleep(network.latency))
+                .await
+                .inspect(|_| timing.simulated_latency += network.latency.as_millis() as u64)
+        };
+
+        let send_start = std::time::Instant::now();
+        // 超过总超时后不再重试
+        let result = if let Err(e) = simulated {
+            Err((e, RetryableFailure::Other))
+        } else if network.should_fail() {
+            Err(("Simulated network failure".to_string(), RetryableFailure::Connect))
+        } else {
+            match within_deadline(deadline, client_config.timeout, attempt_request.send()).await {
+                Ok(Ok(response)) => Ok(response),
+                Ok(Err(e)) if e.is_timeout() => Err((describe_request_error(&e, &client_config), RetryableFailure::Timeout)),
+                Ok(Err(e)) if e.is_connect() => Err((describe_request_error(&e, &client_config), RetryableFailure::Connect)),
+                Ok(Err(e)) => Err((describe_request_error(&e, &client_config), RetryableFailure::Other)),
+                Err(e) => Err((e, RetryableFailure::Other)),
+            }
+        };
+
+        // 等待之后已超过总超时的不再重试
+        let remaining = deadline.map(|deadline| deadline.saturating_duration_since(tokio::time::Instant::now()));
+        let delay = match &result {
+            Ok(response) => retry.next_delay(attempt, RetryableFailure::Status(response.status().as_u16(), response.headers()), remaining),
+            Err((_, failure)) => retry.next_delay(attempt, *failure, remaining),
+        };
+        attempts.push(RetryAttempt {
+            attempt,
+            status: result.as_ref().ok().map(|response| response.status().as_u16()),
+            error: result.as_ref().err().map(|(message, _)| message.clone()),
+            duration: attempt_start.elapsed().as_millis() as u64,
+            delay: delay.map(|delay| delay.as_millis() as u64),
+        });
+
+        match (result, delay) {
+            (Ok(response), None) => {
+                timing.wait = send_start.elapsed().as_millis() as u64;
+                break response;
+            }
+            (Err((message, _)), None) if attempt > 1 => {
+                return Err(format!("Request failed after {} attempts: {}", attempt, message));
+            }
+            (Err((message, _)), None) => return Err(format!("Request failed: {}", message)),
+            (_, Some(delay)) => {
+                log::debug!("Retrying {} in {} ms (attempt {})", config.url, delay.as_millis(), attempt + 1);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        }
+    };
 
     let status = response.status();
+    let remote_addr = match &client_config.socket_path {
+        Some(socket_path) => Some(socket_path.clone()),
+        None => response.remote_addr().map(|addr| addr.to_string()),
+    };
+    let http_version = format_http_version(response.version());
+
+    // 连接复用情况，由连接器中间层按连接记录（Unix socket 等非 TCP 连接没有地址信息）
+    let http_info = response.extensions().get::<HttpInfo>();
+    let connection_reused = http_info.and_then(|info| recorder.mark_used(info));
+
+    // TLS 握手信息，按响应所在的连接查找
+    let tls = match response.version() {
+        // QUIC 固定使用 TLS 1.3 和 ALPN h3，HTTP/3 连接不经过连接器中间层
+        reqwest::Version::HTTP_3 => Some(TlsDetails {
+            protocol_version: Some("TLSv1.3".to_string()),
+            alpn: Some("h3".to_string()),
+            sni: response.url().host_str().map(|host| host.to_string()),
+            ..Default::default()
+        }),
+        _ => http_info.and_then(|info| recorder.details(info)),
+    };
+    let certificates = if config.include_certificates.unwrap_or(false) {
+        http_info.and_then(|info| recorder.certificates(info))
+    } else {
+        None
+    };
     let status_code = status.as_u16();
+    let content_length = response
+        .headers()
+        .get(reqwest::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
     let status_text = status.canonical_reason().unwrap_or("Unknown").to_string();
 
     // Collect headers and cookies
@@ -469,46 +1054,133 @@ pub async fn send_request(config: HttpRequestConfig) -> Result<HttpResponse, Str
 
             // 处理 Set-Cookie header
             if key_str.eq_ignore_ascii_case("set-cookie") {
-                // 解析并保存 cookie
-                if let Some(cookie) = parse_cookie_header(value_str) {
-                    let storage = get_cookies_storage();
-                    let mut storage_guard = storage.lock().unwrap();
-
-                    // 移除同名且同域名的旧 cookie
-                    storage_guard.retain(|c| {
-                        !(c.name == cookie.name && c.domain == cookie.domain)
-                    });
-
-                    storage_guard.push(cookie.clone());
-                    drop(storage_guard);
-
-                    // 标记 cookie 需要保存
-                    mark_cookies_dirty();
-                    log::debug!("Received cookie: {} from {}", cookie.name, cookie.domain);
-                }
+                record_cookie(value_str);
             }
 
             response_headers.insert(key_str, value_str.to_string());
         }
     }
 
-    // Get body as bytes
-    let body_bytes = response
-        .bytes()
-        .await
-        .map_err(|e| format!("Failed to read body: {}", e))?;
+    // Get body as bytes (throttled when a download cap is configured)
+    let download_start = std::time::Instant::now();
+    let mut pacer = DownloadPacer::new(&network);
+    let (limited, size, download) = match &config.download {
+        // 只有成功的响应写入文件，错误页面照常返回；续传时 416 表示文件已经完整
+        Some(options) if status.is_success() || resume.as_ref().is_some_and(|resume| download::is_complete(&response, resume)) => {
+            let reporter = config.request_id.as_deref().map(|request_id| DownloadReporter::new(&app, request_id));
+            let write = download::write_to_file(response, options, &config.url, resume.as_ref(), reporter, &mut pacer);
+            // 大文件下载不受总超时限制，只在显式设置时限制下载时间
+            let write = match options.timeout.and_then(timeout_duration) {
+                Some(timeout) => tokio::time::timeout(timeout, write)
+                    .await
+                    .map_err(|_| format!("Failed to download: Download timeout ({} ms) exceeded", timeout.as_millis()))?,
+                None => write.await,
+            };
+            let (result, preview) = write.map_err(|e| format!("Failed to download: {}", e))?;
+            let size = result.bytes_written as usize;
+            let body = LimitedBody {
+                body: preview,
+                encoded_size: result.bytes_written,
+                decoded_size: result.bytes_written,
+                ..Default::default()
+            };
+            (body, size, Some(result))
+        }
+        _ => {
+            let decoder = Decoder::for_response(response.headers());
+            let keep_raw = client_config.encoding.include_raw_body;
+            let read = limit::read_body(response, &client_config.response_limit, decoder, keep_raw, &mut pacer);
+            let limited = match within_deadline(deadline, client_config.timeout, read).await {
+                Ok(Ok(limited)) => limited,
+                Ok(Err(ReadBodyError::Http(e))) => {
+                    return Err(format!("Failed to read body: {}", describe_request_error(&e, &client_config)));
+                }
+                Ok(Err(ReadBodyError::Other(e))) | Err(e) => return Err(format!("Failed to read body: {}", e)),
+            };
+            let size = limited.body.len();
+            (limited, size, None)
+        }
+    };
+    let LimitedBody {
+        body: body_vec,
+        truncated,
+        spill,
+        encoded_size,
+        decoded_size,
+        raw_body,
+        raw_truncated,
+    } = limited;
+    let spill_path = spill.as_ref().map(|spill| spill.path().to_string_lossy().to_string());
+
+    let content_type = response_headers.get("content-type").map(String::as_str);
+    // 下载时只有文件开头的预览，末尾同样可能被截断
+    let partial = truncated || download.is_some();
+    let text = charset::decode_text(&body_vec, partial, content_type, charset_override);
+    let content_info = sniff::detect(content_type, &body_vec, partial);
+    let decoded_body = if partial {
+        None
+    } else {
+        decode_structured_body(&body_vec, content_type, &content_info, config.response_message_type.as_deref())
+    };
+
+    // 二进制 IPC：响应体和原始响应体都留在后端，通过 fetch_response_body 获取，避免序列化成 JSON
+    // 临时文件跟随缓存项，释放或淘汰时删除
+    let store_body = |body_vec: Vec<u8>| match spill {
+        Some(spill) => body_cache::store_spilled(body_vec, spill),
+        None => body_cache::store(body_vec),
+    };
+    let (body_vec, body_id, raw_body, raw_body_id, text, text_encoding) = if config.binary_body.unwrap_or(false) {
+        let raw_body_id = raw_body.map(body_cache::store).transpose()?;
+        let text_encoding = text.map(|text| text.encoding);
+        (Vec::new(), Some(store_body(body_vec)?), None, raw_body_id, None, text_encoding)
+    } else if spill_path.is_some() {
+        let body_id = store_body(body_vec.clone())?;
+        (body_vec, Some(body_id), raw_body, None, text, None)
+    } else {
+        (body_vec, None, raw_body, None, text, None)
+    };
+
+    let request_body = body.as_ref().map(|body| RequestBodySize {
+        original_size: body.original_len(),
+        sent_size: body.len(),
+        content_encoding: compression.map(|coding| coding.token().to_string()),
+    });
 
-    let body_vec: Vec<u8> = body_bytes.to_vec();
-    let size = body_vec.len();
     let duration = start.elapsed().as_millis() as u64;
+    timing.download = download_start.elapsed().as_millis() as u64;
+    timing.download_throttle = pacer.throttled.as_millis() as u64;
+    timing.upload_throttle = upload_pacer.throttled().as_millis() as u64;
+    timing.total = duration;
 
     Ok(HttpResponse {
         status: status_code,
         status_text,
         headers: response_headers,
         body: body_vec,
+        body_id,
         size,
+        content_length,
+        truncated,
+        spill_path,
+        encoded_size,
+        decoded_size,
+        raw_body,
+        raw_body_id,
+        raw_body_truncated: raw_truncated,
+        text,
+        text_encoding,
+        content_info,
+        decoded_body,
+        request_body,
         duration,
+        remote_addr,
+        http_version,
+        connection_reused,
+        tls,
+        certificates,
+        attempts,
+        timing,
+        download,
     })
 }
 
@@ -553,6 +1225,47 @@ pub fn delete_cookie(domain: String, name: String) -> Result<(), String> {
     Ok(())
 }
 
+// 解析并保存 cookie
+fn record_cookie(header_value: &str) {
+    if let Some(cookie) = parse_cookie_header(header_value) {
+        let storage = get_cookies_storage();
+        let mut storage_guard = storage.lock().unwrap();
+
+        // 移除同名且同域名的旧 cookie
+        storage_guard.retain(|c| {
+            !(c.name == cookie.name && c.domain == cookie.domain)
+        });
+
+        storage_guard.push(cookie.clone());
+        drop(storage_guard);
+
+        // 标记 cookie 需要保存
+        mark_cookies_dirty();
+        log::debug!("Received cookie: {} from {}", cookie.name, cookie.domain);
+    }
+}
+
+/// 共享 cookie jar 中应发往该 URL 的 Cookie 头
+pub(crate) fn cookie_header(url: &reqwest::Url) -> Option<reqwest::header::HeaderValue> {
+    use reqwest::cookie::CookieStore;
+    get_cookie_jar().cookies(url)
+}
+
+/// 保存不经过 reqwest 的响应（如 WebSocket 握手）中的 Set-Cookie
+pub(crate) fn store_response_cookies(url: &reqwest::Url, headers: &reqwest::header::HeaderMap) {
+    use reqwest::cookie::CookieStore;
+    let values: Vec<_> = headers.get_all(reqwest::header::SET_COOKIE).iter().collect();
+    if values.is_empty() {
+        return;
+    }
+    get_cookie_jar().set_cookies(&mut values.iter().copied(), url);
+    for value in values {
+        if let Ok(value_str) = value.to_str() {
+            record_cookie(value_str);
+        }
+    }
+}
+
 // 解析 Set-Cookie header
 fn parse_cookie_header(header_value: &str) -> Option<CookieInfo> {
     let parts: Vec<&str> = header_value.split(';').collect();
@@ -1,5 +1,32 @@
+mod body;
+mod body_cache;
+mod charset;
+mod codec;
+mod compression;
+mod dns;
+mod download;
+mod graphql;
+mod grpc;
 mod http_client;
+mod limit;
+mod mqtt;
+mod progress;
+mod proto;
+mod retry;
+mod sniff;
+mod socketio;
+mod throttle;
+mod tls;
+mod ws;
 use http_client::{send_request, clear_cookies, get_all_cookies, update_config, get_config, init_cookie_storage, delete_cookie, save_cookies_now};
+use body_cache::{fetch_response_body, release_response_body};
+use codec::{decode_binary_body, encode_json_body};
+use graphql::{clear_graphql_schema_cache, get_cached_graphql_schema, graphql_subscribe, graphql_unsubscribe, introspect_graphql_schema};
+use grpc::{grpc_call, list_grpc_services};
+use mqtt::{mqtt_connect, mqtt_disconnect, mqtt_publish, mqtt_subscribe, mqtt_unsubscribe};
+use proto::{clear_proto_registry, list_proto_messages, load_descriptor_set, load_proto_files};
+use socketio::{socketio_ack, socketio_connect, socketio_disconnect, socketio_emit, socketio_join, socketio_leave};
+use tls::inspect_certificate;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -8,7 +35,7 @@ pub fn run() {
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
-        .invoke_handler(tauri::generate_handler![send_request, clear_cookies, get_all_cookies, update_config, get_config, init_cookie_storage, delete_cookie, save_cookies_now])
+        .invoke_handler(tauri::generate_handler![send_request, clear_cookies, get_all_cookies, update_config, get_config, init_cookie_storage, delete_cookie, save_cookies_now, inspect_certificate, fetch_response_body, release_response_body, encode_json_body, decode_binary_body, load_proto_files, load_descriptor_set, list_proto_messages, clear_proto_registry, list_grpc_services, grpc_call, introspect_graphql_schema, get_cached_graphql_schema, clear_graphql_schema_cache, graphql_subscribe, graphql_unsubscribe, socketio_connect, socketio_emit, socketio_ack, socketio_join, socketio_leave, socketio_disconnect, mqtt_connect, mqtt_subscribe, mqtt_unsubscribe, mqtt_publish, mqtt_disconnect])
         .setup(|app| {
             if cfg!(debug_assertions) {
                 app.handle().plugin(
@@ -19,6 +46,12 @@ pub fn run() {
             }
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_, event| {
+            // 退出前删除超出大小限制时写入的临时文件
+            if let tauri::RunEvent::Exit = event {
+                body_cache::clear();
+            }
+        });
 }
@@ -0,0 +1,305 @@
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_util::io::StreamReader;
+
+use crate::compression::Decoder;
+use crate::throttle::DownloadPacer;
+
+static NEXT_SPILL_ID: AtomicU64 = AtomicU64::new(1);
+
+// 解码时每次读取的字节数，超出上限时最多多解码这么多
+const DECODE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// 响应体超过上限时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LimitAction {
+    /// 请求失败
+    #[default]
+    Abort,
+    /// 只保留前 max_size 字节并标记为已截断
+    Truncate,
+    /// 完整内容写入临时文件，响应中只保留前 max_size 字节
+    Spill,
+}
+
+/// 响应体大小限制
+#[derive(Debug, Clone, Default, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ResponseLimit {
+    /// 最大字节数，None 表示不限制
+    pub max_size: Option<u64>,
+    pub action: LimitAction,
+}
+
+/// 按大小限制读取到的响应体
+#[derive(Debug, Default)]
+pub struct LimitedBody {
+    pub body: Vec<u8>,
+    pub truncated: bool,
+    /// 超出上限时保存完整响应体的临时文件
+    pub spill: Option<SpillFile>,
+    /// 从连接读取的字节数（解码前）
+    pub encoded_size: u64,
+    /// 解码后的字节数，截断时只统计已读取的部分
+    pub decoded_size: u64,
+    /// 解码前的原始响应体，同样受大小限制
+    pub raw_body: Option<Vec<u8>>,
+    /// 原始响应体不完整：超出大小限制被截断，或解码后的响应体截断时停止了读取
+    pub raw_truncated: bool,
+}
+
+pub enum ReadBodyError {
+    Http(reqwest::Error),
+    Other(String),
+}
+
+impl From<reqwest::Error> for ReadBodyError {
+    fn from(error: reqwest::Error) -> Self {
+        ReadBodyError::Http(error)
+    }
+}
+
+/// 保存完整响应体或压缩后请求体的临时文件，drop 时删除
+#[derive(Debug)]
+pub struct SpillFile {
+    path: PathBuf,
+}
+
+impl SpillFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => log::debug!("Removed {}", self.path.display()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => log::warn!("Failed to remove {}: {}", self.path.display(), e),
+        }
+    }
+}
+
+/// 在临时目录中创建 `{kind}-{pid}-{id}.bin` 并写入 `prefix`
+pub async fn create_temp_file(kind: &str, prefix: &[u8]) -> Result<(tokio::fs::File, SpillFile), String> {
+    let dir = std::env::temp_dir().join("teapot");
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let id = NEXT_SPILL_ID.fetch_add(1, Ordering::Relaxed);
+    let spill = SpillFile {
+        path: dir.join(format!("{}-{}-{}.bin", kind, std::process::id(), id)),
+    };
+    let mut file = tokio::fs::File::create(spill.path())
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", spill.path().display(), e))?;
+    file.write_all(prefix)
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", spill.path().display(), e))?;
+    Ok((file, spill))
+}
+
+// 按大小限制收集解码后的响应体
+struct Collector<'a> {
+    limit: &'a ResponseLimit,
+    max_size: usize,
+    result: LimitedBody,
+    spill: Option<(tokio::fs::File, SpillFile)>,
+}
+
+impl<'a> Collector<'a> {
+    fn new(limit: &'a ResponseLimit) -> Self {
+        let max_size = limit.max_size.map_or(usize::MAX, |size| usize::try_from(size).unwrap_or(usize::MAX));
+        Collector {
+            limit,
+            max_size,
+            result: LimitedBody::default(),
+            spill: None,
+        }
+    }
+
+    /// 收集一块数据，返回 false 表示不再需要后续内容
+    async fn push(&mut self, chunk: &[u8]) -> Result<bool, ReadBodyError> {
+        self.result.decoded_size += chunk.len() as u64;
+
+        if let Some((file, spill)) = &mut self.spill {
+            file.write_all(chunk)
+                .await
+                .map_err(|e| ReadBodyError::Other(format!("Failed to write {}: {}", spill.path().display(), e)))?;
+            return Ok(true);
+        }
+
+        let remaining = self.max_size - self.result.body.len();
+        if chunk.len() <= remaining {
+            self.result.body.extend_from_slice(chunk);
+            return Ok(true);
+        }
+
+        match self.limit.action {
+            LimitAction::Abort => Err(ReadBodyError::Other(format!(
+                "Response body exceeds the limit of {} bytes",
+                self.max_size
+            ))),
+            LimitAction::Truncate => {
+                self.result.body.extend_from_slice(&chunk[..remaining]);
+                self.result.truncated = true;
+                Ok(false)
+            }
+            LimitAction::Spill => {
+                let (mut file, spill) = create_temp_file("response", &self.result.body).await.map_err(ReadBodyError::Other)?;
+                file.write_all(chunk)
+                    .await
+                    .map_err(|e| ReadBodyError::Other(format!("Failed to write {}: {}", spill.path().display(), e)))?;
+                self.result.body.extend_from_slice(&chunk[..remaining]);
+                self.result.truncated = true;
+                self.spill = Some((file, spill));
+                Ok(true)
+            }
+        }
+    }
+
+    async fn finish(mut self) -> Result<LimitedBody, ReadBodyError> {
+        if let Some((mut file, spill)) = self.spill {
+            file.flush()
+                .await
+                .map_err(|e| ReadBodyError::Other(format!("Failed to write {}: {}", spill.path().display(), e)))?;
+            self.result.spill = Some(spill);
+        }
+        Ok(self.result)
+    }
+}
+
+// 从连接读取编码后的响应体：按下行限速控制速度，统计字节数并按需保留原始内容
+struct Source<'a> {
+    response: reqwest::Response,
+    pacer: &'a mut DownloadPacer,
+    encoded_size: u64,
+    raw_body: Option<Vec<u8>>,
+    raw_truncated: bool,
+    max_size: usize,
+}
+
+impl Source<'_> {
+    async fn next_chunk(&mut self) -> Result<Option<Bytes>, reqwest::Error> {
+        let Some(chunk) = self.response.chunk().await? else {
+            return Ok(None);
+        };
+        self.pacer.consume(chunk.len()).await;
+        self.encoded_size += chunk.len() as u64;
+        if let Some(raw_body) = &mut self.raw_body {
+            let room = self.max_size - raw_body.len();
+            if chunk.len() > room {
+                self.raw_truncated = true;
+            }
+            raw_body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        }
+        Ok(Some(chunk))
+    }
+}
+
+fn decode_read_error(decoder: &Decoder, error: io::Error) -> ReadBodyError {
+    // 连接上的错误原样返回，以便区分超时等情况
+    if !error.get_ref().is_some_and(|inner| inner.is::<reqwest::Error>()) {
+        return ReadBodyError::Other(decoder.decode_error(error));
+    }
+    match error.into_inner().map(|inner| inner.downcast::<reqwest::Error>()) {
+        Some(Ok(error)) => ReadBodyError::Http(*error),
+        Some(Err(inner)) => ReadBodyError::Other(decoder.decode_error(inner)),
+        None => ReadBodyError::Other(decoder.decode_error("unknown error")),
+    }
+}
+
+/// 边读取边解码，每次最多解码 DECODE_CHUNK_SIZE 字节交给 collector，超出上限时立即停止
+async fn decode_into(source: &mut Source<'_>, decoder: &Decoder, collector: &mut Collector<'_>) -> Result<(), ReadBodyError> {
+    // 没有内容时（如 HEAD 或 204）不经过解码器，否则会被当作截断的数据
+    let Some(first) = source.next_chunk().await? else {
+        return Ok(());
+    };
+    let chunks = futures_util::stream::try_unfold((source, Some(first)), |(source, first)| async move {
+        let chunk = match first {
+            Some(chunk) => Some(chunk),
+            None => source.next_chunk().await.map_err(io::Error::other)?,
+        };
+        Ok::<_, io::Error>(chunk.map(|chunk| (chunk, (source, None))))
+    });
+    let mut reader = decoder.reader(StreamReader::new(Box::pin(chunks)));
+
+    let mut buffer = vec![0u8; DECODE_CHUNK_SIZE];
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .await
+            .map_err(|e| decode_read_error(decoder, e))?;
+        if read == 0 || !collector.push(&buffer[..read]).await? {
+            // 截断时不再读取剩余内容，连接随响应一起丢弃
+            return Ok(());
+        }
+    }
+}
+
+/// 读取响应体：按下行限速控制速度，按 Content-Encoding 解码，并按解码后的大小中止、截断或转存到临时文件
+pub async fn read_body(
+    response: reqwest::Response,
+    limit: &ResponseLimit,
+    decoder: Option<Decoder>,
+    keep_raw: bool,
+    pacer: &mut DownloadPacer,
+) -> Result<LimitedBody, ReadBodyError> {
+    if limit.max_size.is_none() && decoder.is_none() && !keep_raw && !pacer.is_limited() {
+        let body = response.bytes().await?.to_vec();
+        let size = body.len() as u64;
+        return Ok(LimitedBody {
+            body,
+            encoded_size: size,
+            decoded_size: size,
+            ..Default::default()
+        });
+    }
+
+    // 未压缩且 Content-Length 已超出上限时无需读取
+    if let Some(max_size) = limit.max_size
+        && limit.action == LimitAction::Abort
+        && decoder.is_none()
+        && let Some(length) = response.content_length()
+        && length > max_size
+    {
+        return Err(ReadBodyError::Other(format!(
+            "Response size {} bytes exceeds the limit of {} bytes",
+            length, max_size
+        )));
+    }
+
+    let mut collector = Collector::new(limit);
+    let mut source = Source {
+        response,
+        pacer,
+        encoded_size: 0,
+        raw_body: keep_raw.then(Vec::new),
+        raw_truncated: false,
+        max_size: collector.max_size,
+    };
+    match &decoder {
+        Some(decoder) => decode_into(&mut source, decoder, &mut collector).await?,
+        None => {
+            while let Some(chunk) = source.next_chunk().await? {
+                if !collector.push(&chunk).await? {
+                    break;
+                }
+            }
+        }
+    }
+
+    collector.result.encoded_size = source.encoded_size;
+    // 截断时剩余内容未读取，原始响应体同样不完整
+    collector.result.raw_truncated =
+        source.raw_body.is_some() && (source.raw_truncated || collector.result.truncated);
+    collector.result.raw_body = source.raw_body;
+    collector.finish().await
+}
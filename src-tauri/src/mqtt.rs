@@ -0,0 +1,875 @@
+use rumqttc::v5::mqttbytes::v5::{
+    LastWill as LastWillV5, Packet as PacketV5, PubAckReason, PubCompReason, Publish as PublishV5,
+    SubscribeReasonCode as SubscribeReasonCodeV5,
+};
+use rumqttc::v5::mqttbytes::QoS as QoSV5;
+use rumqttc::{LastWill, Outgoing, Packet, QoS, SubscribeReasonCode, TlsConfiguration, Transport};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+
+use crate::http_client::{client_config_or_global, timeout_duration, ClientConfig};
+use crate::tls;
+
+/// MQTT 事件名
+pub const MQTT_EVENT: &str = "mqtt-event";
+
+// 未配置超时时等待 CONNACK 和确认报文的时间
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+// 未发出的请求队列长度
+const REQUEST_CAPACITY: usize = 64;
+const DEFAULT_KEEP_ALIVE: u64 = 60;
+const DEFAULT_MAX_PACKET_SIZE: u32 = 1024 * 1024;
+
+#[derive(Clone)]
+struct ConnectionHandle {
+    client: MqttClient,
+    pending: Arc<Mutex<PendingAcks>>,
+    timeout: Duration,
+    /// 区分先后使用同一 connection_id 的连接
+    generation: u64,
+}
+
+// 已建立和正在建立的连接，按 connection_id 索引
+static CONNECTIONS: OnceLock<Mutex<HashMap<String, ConnectionHandle>>> = OnceLock::new();
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+fn get_connections() -> &'static Mutex<HashMap<String, ConnectionHandle>> {
+    CONNECTIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 登记连接并占用 connection_id，ID 已被占用时返回错误
+fn register(
+    connection_id: &str,
+    client: MqttClient,
+    pending: Arc<Mutex<PendingAcks>>,
+    timeout: Duration,
+) -> Result<u64, String> {
+    let mut connections = get_connections().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+    if connections.contains_key(connection_id) {
+        return Err(format!("MQTT connection {} is already open", connection_id));
+    }
+    let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+    let handle = ConnectionHandle {
+        client,
+        pending,
+        timeout,
+        generation,
+    };
+    connections.insert(connection_id.to_string(), handle);
+    Ok(generation)
+}
+
+/// 只在登记的仍是这个连接时移除，不影响之后使用同一 ID 的连接
+fn unregister(connection_id: &str, generation: u64) {
+    if let Ok(mut connections) = get_connections().lock()
+        && connections.get(connection_id).is_some_and(|handle| handle.generation == generation)
+    {
+        connections.remove(connection_id);
+    }
+}
+
+/// MQTT 协议版本
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MqttVersion {
+    #[default]
+    #[serde(rename = "3.1.1")]
+    V311,
+    #[serde(rename = "5")]
+    V5,
+}
+
+/// 遗嘱消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MqttWill {
+    pub topic: String,
+    #[serde(default)]
+    pub payload: Vec<u8>,
+    #[serde(default)]
+    pub qos: u8,
+    #[serde(default)]
+    pub retain: bool,
+}
+
+/// MQTT 连接
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MqttConnectRequest {
+    /// 连接 ID，用于关联事件和后续操作
+    pub connection_id: String,
+    /// Broker 地址，如 `mqtt://localhost:1883`、`mqtts://broker:8883`（也接受 tcp/ssl/tls）
+    pub url: String,
+    #[serde(default)]
+    pub version: MqttVersion,
+    /// 未指定时随机生成
+    pub client_id: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// 心跳间隔（秒），默认 60，0 表示不发送心跳（仅 3.1.1 支持，MQTT 5 至少为 5 秒）
+    pub keep_alive: Option<u64>,
+    /// 3.1.1 的 Clean Session / 5 的 Clean Start，默认 true
+    pub clean_session: Option<bool>,
+    pub will: Option<MqttWill>,
+    /// 单个报文的最大字节数，默认 1 MiB
+    pub max_packet_size: Option<u32>,
+    /// 未设置时使用全局配置（TLS 使用其中的 CA 证书、客户端证书和校验设置）
+    pub config: Option<ClientConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MqttSession {
+    pub client_id: String,
+    /// Broker 保留了之前的会话
+    pub session_present: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MqttPublishRequest {
+    pub connection_id: String,
+    pub topic: String,
+    #[serde(default)]
+    pub payload: Vec<u8>,
+    #[serde(default)]
+    pub qos: u8,
+    #[serde(default)]
+    pub retain: bool,
+}
+
+/// 收到的消息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MqttMessage {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    /// 负载是合法的 UTF-8 时的文本
+    pub text: Option<String>,
+    pub qos: u8,
+    pub retain: bool,
+    pub dup: bool,
+    /// 以下为 MQTT 5 的发布属性
+    pub content_type: Option<String>,
+    pub response_topic: Option<String>,
+    pub correlation_data: Option<Vec<u8>>,
+    pub user_properties: Vec<(String, String)>,
+}
+
+impl MqttMessage {
+    fn new(topic: String, payload: &[u8], qos: u8, retain: bool, dup: bool) -> Self {
+        MqttMessage {
+            topic,
+            payload: payload.to_vec(),
+            text: std::str::from_utf8(payload).ok().map(str::to_string),
+            qos,
+            retain,
+            dup,
+            content_type: None,
+            response_topic: None,
+            correlation_data: None,
+            user_properties: Vec::new(),
+        }
+    }
+
+    fn from_v5(publish: PublishV5) -> Self {
+        let topic = String::from_utf8_lossy(&publish.topic).to_string();
+        let mut message = MqttMessage::new(topic, &publish.payload, publish.qos as u8, publish.retain, publish.dup);
+        if let Some(properties) = publish.properties {
+            message.content_type = properties.content_type;
+            message.response_topic = properties.response_topic;
+            message.correlation_data = properties.correlation_data.map(|data| data.to_vec());
+            message.user_properties = properties.user_properties;
+        }
+        message
+    }
+}
+
+/// MQTT 事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MqttEventKind {
+    Message,
+    /// 连接已关闭，主动断开时 reason 为空
+    Closed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MqttEvent {
+    pub connection_id: String,
+    pub kind: MqttEventKind,
+    pub message: Option<MqttMessage>,
+    pub reason: Option<String>,
+}
+
+fn emit_event(app: &AppHandle, event: MqttEvent) {
+    if let Err(e) = app.emit(MQTT_EVENT, event) {
+        log::warn!("Failed to emit {}: {}", MQTT_EVENT, e);
+    }
+}
+
+// 等待确认的截止时间和请求的结果
+type PendingAck<T> = (Instant, oneshot::Sender<Result<T, String>>);
+
+/// 按发送顺序把请求与报文 ID 对应起来，收到确认时返回结果
+struct AckTracker<T> {
+    /// 已交给事件循环、尚未发出的请求
+    queued: VecDeque<PendingAck<T>>,
+    sent: HashMap<u16, PendingAck<T>>,
+}
+
+impl<T> Default for AckTracker<T> {
+    fn default() -> Self {
+        AckTracker {
+            queued: VecDeque::new(),
+            sent: HashMap::new(),
+        }
+    }
+}
+
+impl<T> AckTracker<T> {
+    fn queue(&mut self, reply: oneshot::Sender<Result<T, String>>, timeout: Duration) {
+        self.queued.push_back((Instant::now() + timeout, reply));
+    }
+
+    fn sent(&mut self, pkid: u16) {
+        if let Some(pending) = self.queued.pop_front() {
+            self.sent.insert(pkid, pending);
+        }
+    }
+
+    fn acked(&mut self, pkid: u16, result: Result<T, String>) {
+        if let Some((_, reply)) = self.sent.remove(&pkid) {
+            let _ = reply.send(result);
+        }
+    }
+
+    /// 移除已发出但超时未确认的请求；未发出的请求需要保留，否则之后的报文 ID 会对应错
+    fn expire(&mut self, now: Instant) {
+        let expired: Vec<u16> = self
+            .sent
+            .iter()
+            .filter(|(_, (deadline, _))| *deadline <= now)
+            .map(|(pkid, _)| *pkid)
+            .collect();
+        for pkid in expired {
+            if let Some((_, reply)) = self.sent.remove(&pkid) {
+                let _ = reply.send(Err("Timed out waiting for acknowledgement".to_string()));
+            }
+        }
+    }
+
+    fn fail_all(&mut self, reason: &str) {
+        for (_, reply) in self.queued.drain(..).chain(self.sent.drain().map(|(_, pending)| pending)) {
+            let _ = reply.send(Err(reason.to_string()));
+        }
+    }
+}
+
+#[derive(Default)]
+struct PendingAcks {
+    /// 返回授予的 QoS
+    subscribes: AckTracker<u8>,
+    /// QoS 1 / 2 的发布
+    publishes: AckTracker<()>,
+}
+
+#[derive(Clone)]
+enum MqttClient {
+    V4(rumqttc::AsyncClient),
+    V5(rumqttc::v5::AsyncClient),
+}
+
+impl MqttClient {
+    fn try_subscribe(&self, topic: &str, qos: u8) -> Result<(), String> {
+        match self {
+            MqttClient::V4(client) => client.try_subscribe(topic, qos_v4(qos)?).map_err(|e| e.to_string()),
+            MqttClient::V5(client) => client.try_subscribe(topic, qos_v5(qos)?).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| format!("Failed to subscribe: {}", e))
+    }
+
+    fn try_publish(&self, topic: &str, qos: u8, retain: bool, payload: Vec<u8>) -> Result<(), String> {
+        match self {
+            MqttClient::V4(client) => client.try_publish(topic, qos_v4(qos)?, retain, payload).map_err(|e| e.to_string()),
+            MqttClient::V5(client) => client.try_publish(topic, qos_v5(qos)?, retain, payload).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| format!("Failed to publish: {}", e))
+    }
+
+    async fn unsubscribe(&self, topic: &str) -> Result<(), String> {
+        match self {
+            MqttClient::V4(client) => client.unsubscribe(topic).await.map_err(|e| e.to_string()),
+            MqttClient::V5(client) => client.unsubscribe(topic).await.map_err(|e| e.to_string()),
+        }
+        .map_err(|e| format!("Failed to unsubscribe: {}", e))
+    }
+
+    async fn disconnect(&self) -> Result<(), String> {
+        match self {
+            MqttClient::V4(client) => client.disconnect().await.map_err(|e| e.to_string()),
+            MqttClient::V5(client) => client.disconnect().await.map_err(|e| e.to_string()),
+        }
+        .map_err(|e| format!("Failed to disconnect: {}", e))
+    }
+}
+
+fn qos_v4(qos: u8) -> Result<QoS, String> {
+    match qos {
+        0 => Ok(QoS::AtMostOnce),
+        1 => Ok(QoS::AtLeastOnce),
+        2 => Ok(QoS::ExactlyOnce),
+        _ => Err(format!("Invalid QoS: {}", qos)),
+    }
+}
+
+fn qos_v5(qos: u8) -> Result<QoSV5, String> {
+    match qos {
+        0 => Ok(QoSV5::AtMostOnce),
+        1 => Ok(QoSV5::AtLeastOnce),
+        2 => Ok(QoSV5::ExactlyOnce),
+        _ => Err(format!("Invalid QoS: {}", qos)),
+    }
+}
+
+// 两个协议版本的事件统一后的形式
+enum LoopEvent {
+    ConnAck { session_present: bool },
+    Message(MqttMessage),
+    SubscribeSent(u16),
+    SubAck(u16, Result<u8, String>),
+    PublishSent(u16),
+    /// QoS 1 的 PUBACK 或 QoS 2 的 PUBCOMP
+    PublishAcked(u16, Result<(), String>),
+    ServerDisconnect(String),
+    ClientDisconnect,
+    Other,
+}
+
+enum MqttEventLoop {
+    V4(Box<rumqttc::EventLoop>),
+    V5(Box<rumqttc::v5::EventLoop>),
+}
+
+impl MqttEventLoop {
+    async fn poll(&mut self) -> Result<LoopEvent, String> {
+        match self {
+            MqttEventLoop::V4(event_loop) => match event_loop.poll().await.map_err(|e| e.to_string())? {
+                rumqttc::Event::Incoming(packet) => Ok(match packet {
+                    Packet::ConnAck(ack) => LoopEvent::ConnAck {
+                        session_present: ack.session_present,
+                    },
+                    Packet::Publish(publish) => LoopEvent::Message(MqttMessage::new(
+                        publish.topic,
+                        &publish.payload,
+                        publish.qos as u8,
+                        publish.retain,
+                        publish.dup,
+                    )),
+                    Packet::SubAck(ack) => LoopEvent::SubAck(
+                        ack.pkid,
+                        match ack.return_codes.first() {
+                            Some(SubscribeReasonCode::Success(qos)) => Ok(*qos as u8),
+                            _ => Err("Subscription rejected by broker".to_string()),
+                        },
+                    ),
+                    Packet::PubAck(ack) => LoopEvent::PublishAcked(ack.pkid, Ok(())),
+                    Packet::PubComp(ack) => LoopEvent::PublishAcked(ack.pkid, Ok(())),
+                    _ => LoopEvent::Other,
+                }),
+                rumqttc::Event::Outgoing(outgoing) => Ok(outgoing_event(outgoing)),
+            },
+            MqttEventLoop::V5(event_loop) => match event_loop.poll().await.map_err(|e| e.to_string())? {
+                rumqttc::v5::Event::Incoming(packet) => Ok(match packet {
+                    PacketV5::ConnAck(ack) => LoopEvent::ConnAck {
+                        session_present: ack.session_present,
+                    },
+                    PacketV5::Publish(publish) => LoopEvent::Message(MqttMessage::from_v5(publish)),
+                    PacketV5::SubAck(ack) => LoopEvent::SubAck(
+                        ack.pkid,
+                        match ack.return_codes.first() {
+                            Some(SubscribeReasonCodeV5::Success(qos)) => Ok(*qos as u8),
+                            Some(code) => Err(format!("Subscription rejected by broker: {:?}", code)),
+                            None => Err("Subscription rejected by broker".to_string()),
+                        },
+                    ),
+                    PacketV5::PubAck(ack) => LoopEvent::PublishAcked(
+                        ack.pkid,
+                        match ack.reason {
+                            PubAckReason::Success | PubAckReason::NoMatchingSubscribers => Ok(()),
+                            reason => Err(format!("Publish rejected by broker: {:?}", reason)),
+                        },
+                    ),
+                    PacketV5::PubComp(ack) => LoopEvent::PublishAcked(
+                        ack.pkid,
+                        match ack.reason {
+                            PubCompReason::Success => Ok(()),
+                            reason => Err(format!("Publish rejected by broker: {:?}", reason)),
+                        },
+                    ),
+                    PacketV5::Disconnect(disconnect) => LoopEvent::ServerDisconnect(
+                        disconnect
+                            .properties
+                            .and_then(|properties| properties.reason_string)
+                            .unwrap_or_else(|| format!("{:?}", disconnect.reason_code)),
+                    ),
+                    _ => LoopEvent::Other,
+                }),
+                rumqttc::v5::Event::Outgoing(outgoing) => Ok(outgoing_event(outgoing)),
+            },
+        }
+    }
+}
+
+fn outgoing_event(outgoing: Outgoing) -> LoopEvent {
+    match outgoing {
+        Outgoing::Subscribe(pkid) => LoopEvent::SubscribeSent(pkid),
+        // QoS 0 的报文 ID 为 0，不需要等待确认
+        Outgoing::Publish(pkid) if pkid != 0 => LoopEvent::PublishSent(pkid),
+        Outgoing::Disconnect => LoopEvent::ClientDisconnect,
+        _ => LoopEvent::Other,
+    }
+}
+
+/// 校验心跳间隔，rumqttc 遇到不支持的取值会直接 panic
+fn keep_alive_duration(version: MqttVersion, secs: u64) -> Result<Duration, String> {
+    if secs > u64::from(u16::MAX) {
+        return Err(format!("MQTT keep alive must be at most {} seconds", u16::MAX));
+    }
+    if version == MqttVersion::V5 && secs < 5 {
+        return Err(if secs == 0 {
+            "Disabling keep alive is not supported for MQTT 5 connections".to_string()
+        } else {
+            "MQTT 5 keep alive must be at least 5 seconds".to_string()
+        });
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// 解析 Broker 地址，返回主机、端口和是否使用 TLS
+fn parse_broker_url(url: &str) -> Result<(String, u16, bool), String> {
+    let parsed_url = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+    let secure = match parsed_url.scheme() {
+        "mqtt" | "tcp" => false,
+        "mqtts" | "ssl" | "tls" => true,
+        scheme => return Err(format!("Unsupported MQTT scheme: {}", scheme)),
+    };
+    let host = parsed_url
+        .host_str()
+        .ok_or("URL has no host")?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = parsed_url.port().unwrap_or(if secure { 8883 } else { 1883 });
+    Ok((host, port, secure))
+}
+
+/// 连接 MQTT Broker，收到的消息以 mqtt-event 事件发出。
+/// 连接断开后不会自动重连，需要重新连接并订阅
+#[tauri::command]
+pub async fn mqtt_connect(app: AppHandle, request: MqttConnectRequest) -> Result<MqttSession, String> {
+    connect(request, move |event| emit_event(&app, event)).await
+}
+
+async fn connect(
+    request: MqttConnectRequest,
+    emit: impl Fn(MqttEvent) + Send + 'static,
+) -> Result<MqttSession, String> {
+    let client_config = client_config_or_global(request.config)?;
+    let (host, port, secure) = parse_broker_url(&request.url)?;
+    let transport = if secure {
+        let mut tls_config = tls::build_tls_config(&client_config)?;
+        tls_config.alpn_protocols.clear();
+        Transport::tls_with_config(TlsConfiguration::Rustls(Arc::new(tls_config)))
+    } else {
+        Transport::tcp()
+    };
+
+    let client_id = request
+        .client_id
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(|| format!("teapot-{:08x}", rand::random::<u32>()));
+    let keep_alive = keep_alive_duration(request.version, request.keep_alive.unwrap_or(DEFAULT_KEEP_ALIVE))?;
+    let clean_session = request.clean_session.unwrap_or(true);
+    let max_packet_size = request.max_packet_size.unwrap_or(DEFAULT_MAX_PACKET_SIZE);
+    let credentials = request.username.map(|username| (username, request.password.unwrap_or_default()));
+
+    let (client, mut event_loop) = match request.version {
+        MqttVersion::V311 => {
+            let mut options = rumqttc::MqttOptions::new(&client_id, &host, port);
+            options
+                .set_transport(transport)
+                .set_keep_alive(keep_alive)
+                .set_clean_session(clean_session)
+                .set_max_packet_size(max_packet_size as usize, max_packet_size as usize);
+            if let Some((username, password)) = credentials {
+                options.set_credentials(username, password);
+            }
+            if let Some(will) = request.will {
+                options.set_last_will(LastWill::new(will.topic, will.payload, qos_v4(will.qos)?, will.retain));
+            }
+            let (client, event_loop) = rumqttc::AsyncClient::new(options, REQUEST_CAPACITY);
+            (MqttClient::V4(client), MqttEventLoop::V4(Box::new(event_loop)))
+        }
+        MqttVersion::V5 => {
+            let mut options = rumqttc::v5::MqttOptions::new(&client_id, &host, port);
+            options
+                .set_transport(transport)
+                .set_keep_alive(keep_alive)
+                .set_clean_start(clean_session)
+                .set_max_packet_size(Some(max_packet_size));
+            if let Some((username, password)) = credentials {
+                options.set_credentials(username, password);
+            }
+            if let Some(will) = request.will {
+                options.set_last_will(LastWillV5::new(will.topic, will.payload, qos_v5(will.qos)?, will.retain, None));
+            }
+            let (client, event_loop) = rumqttc::v5::AsyncClient::new(options, REQUEST_CAPACITY);
+            (MqttClient::V5(client), MqttEventLoop::V5(Box::new(event_loop)))
+        }
+    };
+
+    let timeout = timeout_duration(client_config.timeout).unwrap_or(DEFAULT_TIMEOUT);
+    let connect_timeout = client_config.connect_timeout.and_then(timeout_duration).unwrap_or(timeout);
+    // 连接前先占用 ID，期间的订阅和发布在连接建立后发出
+    let pending = Arc::new(Mutex::new(PendingAcks::default()));
+    let generation = register(&request.connection_id, client, pending.clone(), timeout)?;
+    let wait_connack = async {
+        loop {
+            match event_loop.poll().await {
+                Ok(LoopEvent::ConnAck { session_present }) => return Ok(session_present),
+                Ok(_) => {}
+                Err(e) => return Err(format!("Failed to connect to {}:{}: {}", host, port, e)),
+            }
+        }
+    };
+    let session_present = match tokio::time::timeout(connect_timeout, wait_connack).await {
+        Ok(Ok(session_present)) => session_present,
+        Ok(Err(e)) => {
+            unregister(&request.connection_id, generation);
+            return Err(e);
+        }
+        Err(_) => {
+            unregister(&request.connection_id, generation);
+            return Err(format!("Connection to {} timed out", request.url));
+        }
+    };
+
+    tauri::async_runtime::spawn(run_event_loop(emit, request.connection_id, generation, event_loop, pending));
+
+    Ok(MqttSession {
+        client_id,
+        session_present,
+    })
+}
+
+async fn run_event_loop(
+    emit: impl Fn(MqttEvent),
+    connection_id: String,
+    generation: u64,
+    mut event_loop: MqttEventLoop,
+    pending: Arc<Mutex<PendingAcks>>,
+) {
+    let reason = loop {
+        let event = match event_loop.poll().await {
+            Ok(event) => event,
+            Err(e) => break Some(e),
+        };
+        let mut pending = match pending.lock() {
+            Ok(pending) => pending,
+            Err(e) => break Some(format!("Failed to acquire lock: {}", e)),
+        };
+        match event {
+            LoopEvent::Message(message) => emit(MqttEvent {
+                connection_id: connection_id.clone(),
+                kind: MqttEventKind::Message,
+                message: Some(message),
+                reason: None,
+            }),
+            LoopEvent::SubscribeSent(pkid) => pending.subscribes.sent(pkid),
+            LoopEvent::SubAck(pkid, result) => pending.subscribes.acked(pkid, result),
+            LoopEvent::PublishSent(pkid) => pending.publishes.sent(pkid),
+            LoopEvent::PublishAcked(pkid, result) => pending.publishes.acked(pkid, result),
+            LoopEvent::ServerDisconnect(reason) => break Some(format!("Disconnected by broker: {}", reason)),
+            LoopEvent::ClientDisconnect => break None,
+            LoopEvent::ConnAck { .. } | LoopEvent::Other => {}
+        }
+        // poll 不能被中途取消，超时的确认在每次事件后清理（至少每个 keep alive 周期一次）
+        let now = Instant::now();
+        pending.subscribes.expire(now);
+        pending.publishes.expire(now);
+    };
+
+    unregister(&connection_id, generation);
+    if let Ok(mut pending) = pending.lock() {
+        let message = reason.as_deref().unwrap_or("Connection closed");
+        pending.subscribes.fail_all(message);
+        pending.publishes.fail_all(message);
+    }
+    emit(MqttEvent {
+        connection_id,
+        kind: MqttEventKind::Closed,
+        message: None,
+        reason,
+    });
+}
+
+fn get_connection(connection_id: &str) -> Result<ConnectionHandle, String> {
+    let connections = get_connections().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+    connections
+        .get(connection_id)
+        .cloned()
+        .ok_or_else(|| format!("MQTT connection {} is not open", connection_id))
+}
+
+async fn wait_ack<T>(result: oneshot::Receiver<Result<T, String>>, timeout: Duration) -> Result<T, String> {
+    tokio::time::timeout(timeout, result)
+        .await
+        .map_err(|_| "Timed out waiting for acknowledgement".to_string())?
+        .map_err(|_| "MQTT connection closed".to_string())?
+}
+
+/// 订阅主题（支持 `+` 和 `#` 通配符），返回 Broker 授予的 QoS
+#[tauri::command]
+pub async fn mqtt_subscribe(connection_id: String, topic: String, qos: Option<u8>) -> Result<u8, String> {
+    if !rumqttc::valid_filter(&topic) {
+        return Err(format!("Invalid topic filter: {}", topic));
+    }
+    let connection = get_connection(&connection_id)?;
+    let (reply, result) = oneshot::channel();
+    {
+        // 持有锁直到请求入队，保证确认与请求按顺序对应
+        let mut pending = connection.pending.lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+        connection.client.try_subscribe(&topic, qos.unwrap_or(0))?;
+        pending.subscribes.queue(reply, connection.timeout);
+    }
+    wait_ack(result, connection.timeout).await
+}
+
+#[tauri::command]
+pub async fn mqtt_unsubscribe(connection_id: String, topic: String) -> Result<(), String> {
+    get_connection(&connection_id)?.client.unsubscribe(&topic).await
+}
+
+/// 发布消息；QoS 1 / 2 时等待 Broker 确认
+#[tauri::command]
+pub async fn mqtt_publish(request: MqttPublishRequest) -> Result<(), String> {
+    if !rumqttc::valid_topic(&request.topic) {
+        return Err(format!("Invalid topic: {}", request.topic));
+    }
+    let connection = get_connection(&request.connection_id)?;
+    if request.qos == 0 {
+        return connection.client.try_publish(&request.topic, 0, request.retain, request.payload);
+    }
+
+    let (reply, result) = oneshot::channel();
+    {
+        let mut pending = connection.pending.lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+        connection
+            .client
+            .try_publish(&request.topic, request.qos, request.retain, request.payload)?;
+        pending.publishes.queue(reply, connection.timeout);
+    }
+    wait_ack(result, connection.timeout).await
+}
+
+/// 发送 DISCONNECT 并关闭连接
+#[tauri::command]
+pub async fn mqtt_disconnect(connection_id: String) -> Result<(), String> {
+    get_connection(&connection_id)?.client.disconnect().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn parses_broker_urls() {
+        assert_eq!(parse_broker_url("mqtt://localhost").unwrap(), ("localhost".to_string(), 1883, false));
+        assert_eq!(parse_broker_url("tcp://10.0.0.1:1884").unwrap(), ("10.0.0.1".to_string(), 1884, false));
+        assert_eq!(parse_broker_url("mqtts://broker.example.com").unwrap(), ("broker.example.com".to_string(), 8883, true));
+        assert_eq!(parse_broker_url("ssl://broker:9883").unwrap(), ("broker".to_string(), 9883, true));
+        assert_eq!(parse_broker_url("tls://[::1]").unwrap(), ("::1".to_string(), 8883, true));
+        assert!(parse_broker_url("http://localhost").is_err());
+        assert!(parse_broker_url("localhost:1883").is_err());
+    }
+
+    #[test]
+    fn maps_qos_levels() {
+        assert_eq!(qos_v4(0).unwrap(), QoS::AtMostOnce);
+        assert_eq!(qos_v4(1).unwrap(), QoS::AtLeastOnce);
+        assert_eq!(qos_v4(2).unwrap(), QoS::ExactlyOnce);
+        assert!(qos_v4(3).is_err());
+        assert_eq!(qos_v5(0).unwrap(), QoSV5::AtMostOnce);
+        assert_eq!(qos_v5(1).unwrap(), QoSV5::AtLeastOnce);
+        assert_eq!(qos_v5(2).unwrap(), QoSV5::ExactlyOnce);
+        assert!(qos_v5(3).is_err());
+    }
+
+    #[test]
+    fn validates_keep_alive() {
+        assert_eq!(keep_alive_duration(MqttVersion::V311, 0).unwrap(), Duration::ZERO);
+        assert_eq!(keep_alive_duration(MqttVersion::V311, 1).unwrap(), Duration::from_secs(1));
+        assert!(keep_alive_duration(MqttVersion::V5, 0).is_err());
+        assert!(keep_alive_duration(MqttVersion::V5, 4).is_err());
+        assert_eq!(keep_alive_duration(MqttVersion::V5, 5).unwrap(), Duration::from_secs(5));
+        assert!(keep_alive_duration(MqttVersion::V311, 65536).is_err());
+    }
+
+    #[test]
+    fn ack_tracker_matches_requests_in_send_order() {
+        let mut tracker = AckTracker::<u8>::default();
+        let (first, mut first_result) = oneshot::channel();
+        let (second, mut second_result) = oneshot::channel();
+        let (third, mut third_result) = oneshot::channel();
+        for reply in [first, second, third] {
+            tracker.queue(reply, Duration::from_secs(30));
+        }
+
+        tracker.sent(7);
+        tracker.sent(3);
+        // 未知的报文 ID 不影响其他请求
+        tracker.acked(42, Ok(0));
+        tracker.acked(3, Ok(1));
+        tracker.acked(7, Err("rejected".to_string()));
+
+        assert_eq!(first_result.try_recv().unwrap(), Err("rejected".to_string()));
+        assert_eq!(second_result.try_recv().unwrap(), Ok(1));
+        assert!(third_result.try_recv().is_err());
+
+        tracker.fail_all("closed");
+        assert_eq!(third_result.try_recv().unwrap(), Err("closed".to_string()));
+        assert!(tracker.queued.is_empty() && tracker.sent.is_empty());
+    }
+
+    #[test]
+    fn ack_tracker_expires_only_sent_requests() {
+        let mut tracker = AckTracker::<()>::default();
+        let (sent, mut sent_result) = oneshot::channel();
+        let (queued, mut queued_result) = oneshot::channel();
+        tracker.queue(sent, Duration::ZERO);
+        tracker.queue(queued, Duration::ZERO);
+        tracker.sent(1);
+
+        tracker.expire(Instant::now());
+        assert_eq!(sent_result.try_recv().unwrap(), Err("Timed out waiting for acknowledgement".to_string()));
+        assert!(tracker.sent.is_empty());
+        // 尚未发出的请求保留，之后仍按顺序对应报文 ID
+        assert!(queued_result.try_recv().is_err());
+        tracker.sent(2);
+        tracker.acked(2, Ok(()));
+        assert_eq!(queued_result.try_recv().unwrap(), Ok(()));
+    }
+
+    fn connect_request(url: &str, connection_id: &str, version: MqttVersion) -> MqttConnectRequest {
+        MqttConnectRequest {
+            connection_id: connection_id.to_string(),
+            url: url.to_string(),
+            version,
+            client_id: Some(connection_id.replace('/', "-")),
+            username: None,
+            password: None,
+            keep_alive: None,
+            clean_session: None,
+            will: None,
+            max_packet_size: None,
+            config: None,
+        }
+    }
+
+    async fn connect_collecting(request: MqttConnectRequest) -> mpsc::UnboundedReceiver<MqttEvent> {
+        let (events, received) = mpsc::unbounded_channel();
+        connect(request, move |event| {
+            let _ = events.send(event);
+        })
+        .await
+        .unwrap();
+        received
+    }
+
+    async fn next_event(events: &mut mpsc::UnboundedReceiver<MqttEvent>) -> MqttEvent {
+        tokio::time::timeout(Duration::from_secs(10), events.recv())
+            .await
+            .expect("timed out waiting for event")
+            .expect("event channel closed")
+    }
+
+    async fn publish(connection_id: &str, topic: &str, payload: &[u8], qos: u8, retain: bool) {
+        mqtt_publish(MqttPublishRequest {
+            connection_id: connection_id.to_string(),
+            topic: topic.to_string(),
+            payload: payload.to_vec(),
+            qos,
+            retain,
+        })
+        .await
+        .unwrap();
+    }
+
+    /// Broker 需要在同一端口同时支持 3.1.1 和 5（如 mosquitto），
+    /// 例如 `MQTT_TEST_URL=mqtt://localhost:1883 cargo test mqtt -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires an MQTT broker at MQTT_TEST_URL"]
+    async fn round_trip_against_broker() {
+        let url = std::env::var("MQTT_TEST_URL").expect("MQTT_TEST_URL is not set");
+
+        for version in [MqttVersion::V311, MqttVersion::V5] {
+            let prefix = format!("teapot-test/{:08x}", rand::random::<u32>());
+            let subscriber = format!("{}/subscriber", prefix);
+            let publisher = format!("{}/publisher", prefix);
+
+            let mut subscriber_events = connect_collecting(connect_request(&url, &subscriber, version)).await;
+            let mut publisher_events = connect_collecting(connect_request(&url, &publisher, version)).await;
+
+            let granted = mqtt_subscribe(subscriber.clone(), format!("{}/+/temp", prefix), Some(2)).await.unwrap();
+            assert!(granted >= 1, "broker granted QoS {}", granted);
+
+            publish(&publisher, &format!("{}/kitchen/humidity", prefix), b"40", 1, false).await;
+            publish(&publisher, &format!("{}/kitchen/temp", prefix), b"21", 1, false).await;
+            publish(&publisher, &format!("{}/hall/temp", prefix), b"19", 2, false).await;
+
+            let mut received = Vec::new();
+            for _ in 0..2 {
+                let event = next_event(&mut subscriber_events).await;
+                assert_eq!(event.kind, MqttEventKind::Message);
+                let message = event.message.unwrap();
+                received.push((message.topic, message.text.unwrap()));
+            }
+            received.sort();
+            assert_eq!(
+                received,
+                [
+                    (format!("{}/hall/temp", prefix), "19".to_string()),
+                    (format!("{}/kitchen/temp", prefix), "21".to_string()),
+                ]
+            );
+
+            // 保留消息会发给之后的订阅者
+            let retained = format!("{}/retained", prefix);
+            publish(&publisher, &retained, b"on", 1, true).await;
+            mqtt_subscribe(subscriber.clone(), retained.clone(), Some(1)).await.unwrap();
+            let message = next_event(&mut subscriber_events).await.message.unwrap();
+            assert_eq!(message.topic, retained);
+            assert_eq!(message.payload, b"on");
+            assert!(message.retain);
+            publish(&publisher, &retained, b"", 1, true).await;
+
+            for (connection_id, events) in [(&subscriber, &mut subscriber_events), (&publisher, &mut publisher_events)] {
+                mqtt_disconnect(connection_id.clone()).await.unwrap();
+                let event = loop {
+                    let event = next_event(events).await;
+                    if event.kind == MqttEventKind::Closed {
+                        break event;
+                    }
+                };
+                assert_eq!(event.reason, None);
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::body::ByteStream;
+
+/// 上传进度事件名
+pub const UPLOAD_PROGRESS_EVENT: &str = "upload-progress";
+/// 下载进度事件名
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "download-progress";
+
+// 两次进度事件之间的最小间隔，避免事件过多拖慢界面
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 上传进度，`sent` 为已交给连接发送的字节数
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadProgress {
+    pub request_id: String,
+    pub sent: u64,
+    pub total: u64,
+}
+
+/// 下载进度，续传时 `received` 包含之前已下载的部分
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgress {
+    pub request_id: String,
+    pub received: u64,
+    pub total: Option<u64>,
+}
+
+/// 限制事件频率的进度上报，最后一次总会发出
+struct ProgressEmitter {
+    app: AppHandle,
+    event: &'static str,
+    last_emit: Option<Instant>,
+}
+
+impl ProgressEmitter {
+    fn new(app: &AppHandle, event: &'static str) -> Self {
+        ProgressEmitter {
+            app: app.clone(),
+            event,
+            last_emit: None,
+        }
+    }
+
+    fn emit<S: Serialize + Clone>(&mut self, payload: impl FnOnce() -> S, finished: bool) {
+        if !finished && self.last_emit.is_some_and(|last| last.elapsed() < PROGRESS_INTERVAL) {
+            return;
+        }
+        self.last_emit = Some(Instant::now());
+
+        if let Err(e) = self.app.emit(self.event, payload()) {
+            log::warn!("Failed to emit {}: {}", self.event, e);
+        }
+    }
+}
+
+/// 按块发送请求体，并在发送过程中发出上传进度事件；压缩时按压缩前的字节数计算
+pub fn upload_body(app: &AppHandle, request_id: &str, total: u64, chunks: ByteStream) -> ByteStream {
+    let request_id = request_id.to_string();
+    let mut emitter = ProgressEmitter::new(app, UPLOAD_PROGRESS_EVENT);
+    let mut emit = move |sent: u64| {
+        let progress = || UploadProgress {
+            request_id: request_id.clone(),
+            sent,
+            total,
+        };
+        emitter.emit(progress, sent == total);
+    };
+
+    emit(0);
+    let mut sent = 0;
+    let stream = chunks.map(move |chunk| {
+        if let Ok(chunk) = &chunk {
+            sent += chunk.len() as u64;
+            emit(sent);
+        }
+        chunk
+    });
+    stream.boxed()
+}
+
+/// 下载进度上报
+pub struct DownloadReporter {
+    emitter: ProgressEmitter,
+    request_id: String,
+}
+
+impl DownloadReporter {
+    pub fn new(app: &AppHandle, request_id: &str) -> Self {
+        DownloadReporter {
+            emitter: ProgressEmitter::new(app, DOWNLOAD_PROGRESS_EVENT),
+            request_id: request_id.to_string(),
+        }
+    }
+
+    pub fn report(&mut self, received: u64, total: Option<u64>, finished: bool) {
+        let progress = || DownloadProgress {
+            request_id: self.request_id.clone(),
+            received,
+            total,
+        };
+        self.emitter.emit(progress, finished);
+    }
+}
@@ -0,0 +1,145 @@
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, SerializeOptions};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+// 已加载的 .proto 文件和描述符集合
+static DESCRIPTOR_POOL: OnceLock<Mutex<DescriptorPool>> = OnceLock::new();
+
+fn get_descriptor_pool() -> &'static Mutex<DescriptorPool> {
+    DESCRIPTOR_POOL.get_or_init(|| Mutex::new(DescriptorPool::new()))
+}
+
+/// 把新的文件描述符加入注册表，同名文件以新加载的为准
+fn register(files: Vec<prost_types::FileDescriptorProto>) -> Result<Vec<String>, String> {
+    let mut pool = get_descriptor_pool().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+
+    let replaced: HashSet<&str> = files.iter().map(|file| file.name()).collect();
+    let kept = pool
+        .files()
+        .filter(|file| !replaced.contains(file.name()))
+        .map(|file| file.file_descriptor_proto().clone());
+
+    let mut updated = DescriptorPool::new();
+    updated
+        .add_file_descriptor_protos(kept.chain(files.iter().cloned()))
+        .map_err(|e| format!("Invalid descriptor: {}", e))?;
+    *pool = updated;
+
+    Ok(pool
+        .files()
+        .filter(|file| replaced.contains(file.name()))
+        .flat_map(|file| file.messages().map(|message| message.full_name().to_string()).collect::<Vec<_>>())
+        .collect())
+}
+
+/// 当前注册表的快照
+pub fn descriptor_pool() -> Result<DescriptorPool, String> {
+    let pool = get_descriptor_pool().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+    Ok(pool.clone())
+}
+
+/// 按完整名称（如 `acme.v1.Order`）查找消息类型
+pub fn message_descriptor(message_type: &str) -> Result<MessageDescriptor, String> {
+    let pool = get_descriptor_pool().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+    pool.get_message_by_name(message_type.trim_start_matches('.'))
+        .ok_or_else(|| format!("Unknown message type: {}", message_type))
+}
+
+/// 按 proto3 JSON 映射把 JSON 编码为 protobuf
+pub fn encode_json(json: &str, message_type: &str) -> Result<Vec<u8>, String> {
+    let message = message_from_json(message_descriptor(message_type)?, json)?;
+    Ok(message.encode_to_vec())
+}
+
+/// 按 proto3 JSON 映射解析消息
+pub fn message_from_json(descriptor: MessageDescriptor, json: &str) -> Result<DynamicMessage, String> {
+    let message_type = descriptor.full_name().to_string();
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    DynamicMessage::deserialize(descriptor, &mut deserializer)
+        .and_then(|message| deserializer.end().map(|_| message))
+        .map_err(|e| format!("Invalid JSON for {}: {}", message_type, e))
+}
+
+/// 把 protobuf 解码为格式化的 JSON，保留默认值字段便于查看
+pub fn decode_to_json(data: &[u8], message_type: &str) -> Result<String, String> {
+    let descriptor = message_descriptor(message_type)?;
+    let message =
+        DynamicMessage::decode(descriptor, data).map_err(|e| format!("Failed to decode {}: {}", message_type, e))?;
+    message_to_json(&message)
+}
+
+/// 把消息格式化为 JSON，保留默认值字段
+pub fn message_to_json(message: &DynamicMessage) -> Result<String, String> {
+    let options = SerializeOptions::new().skip_default_fields(false);
+    let mut output = Vec::new();
+    let mut serializer = serde_json::Serializer::pretty(&mut output);
+    message
+        .serialize_with_options(&mut serializer, &options)
+        .map_err(|e| format!("Failed to format JSON: {}", e))?;
+    String::from_utf8(output).map_err(|e| format!("Failed to format JSON: {}", e))
+}
+
+/// Content-Type 是否为 protobuf
+pub fn is_protobuf_content_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    matches!(
+        mime.as_str(),
+        "application/x-protobuf" | "application/protobuf" | "application/x-protobuffer" | "application/vnd.google.protobuf"
+    )
+}
+
+/// Content-Type 参数中声明的消息类型，如 `application/x-protobuf; messageType="acme.v1.Order"`
+pub fn content_type_message(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        let name = name.trim();
+        (name.eq_ignore_ascii_case("messagetype") || name.eq_ignore_ascii_case("proto"))
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// 编译 .proto 文件并注册其中的消息类型，返回新注册的消息名称；
+/// 未指定导入目录时使用各文件所在目录
+#[tauri::command]
+pub fn load_proto_files(files: Vec<String>, include_paths: Option<Vec<String>>) -> Result<Vec<String>, String> {
+    let includes = include_paths.unwrap_or_else(|| {
+        let mut parents: Vec<String> = files
+            .iter()
+            .filter_map(|file| Path::new(file).parent())
+            .map(|parent| parent.to_string_lossy().to_string())
+            .collect();
+        parents.sort();
+        parents.dedup();
+        parents
+    });
+    let file_set = protox::compile(&files, &includes).map_err(|e| format!("Failed to compile proto files: {}", e))?;
+    register(file_set.file)
+}
+
+/// 加载 `protoc --descriptor_set_out` 生成的描述符集合（建议带 `--include_imports`）
+#[tauri::command]
+pub fn load_descriptor_set(path: String) -> Result<Vec<String>, String> {
+    let data = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let file_set = prost_types::FileDescriptorSet::decode(data.as_slice())
+        .map_err(|e| format!("Invalid descriptor set {}: {}", path, e))?;
+    register(file_set.file)
+}
+
+/// 列出已注册的全部消息类型
+#[tauri::command]
+pub fn list_proto_messages() -> Result<Vec<String>, String> {
+    let pool = get_descriptor_pool().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+    let mut messages: Vec<String> = pool.all_messages().map(|message| message.full_name().to_string()).collect();
+    messages.sort();
+    Ok(messages)
+}
+
+/// 清空已注册的 .proto 文件
+#[tauri::command]
+pub fn clear_proto_registry() -> Result<(), String> {
+    let mut pool = get_descriptor_pool().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+    *pool = DescriptorPool::new();
+    Ok(())
+}
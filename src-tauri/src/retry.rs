@@ -0,0 +1,222 @@
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 自动重试策略
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetryPolicy {
+    /// 最大尝试次数（含首次请求），1 表示不重试
+    pub max_attempts: u32,
+    /// 连接失败时重试
+    pub retry_on_connect_error: bool,
+    /// 连接或读取超时时重试，超过总超时后不再重试
+    pub retry_on_timeout: bool,
+    /// 需要重试的响应状态码
+    pub retry_status_codes: Vec<u16>,
+    /// 首次重试前的等待时间（毫秒），之后每次翻倍
+    pub initial_delay: u64,
+    /// 单次等待的上限（毫秒）
+    pub max_delay: u64,
+    /// 在退避时间上增加随机抖动，避免多个客户端同时重试
+    pub jitter: bool,
+    /// 按响应的 Retry-After 头决定等待时间
+    pub respect_retry_after: bool,
+    /// 也重试 POST、PATCH 等非幂等请求，服务器可能会重复处理
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            retry_on_connect_error: true,
+            retry_on_timeout: true,
+            retry_status_codes: vec![429, 502, 503, 504],
+            initial_delay: 500,
+            max_delay: 30000,
+            jitter: true,
+            respect_retry_after: true,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+/// 一次请求尝试的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryAttempt {
+    /// 第几次尝试，从 1 开始
+    pub attempt: u32,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    /// 本次尝试耗时（毫秒）
+    pub duration: u64,
+    /// 下一次尝试前的等待时间（毫秒），最后一次为 None
+    pub delay: Option<u64>,
+}
+
+/// 请求失败的类型，用于判断是否可以重试
+#[derive(Clone, Copy)]
+pub enum RetryableFailure<'a> {
+    Connect,
+    Timeout,
+    Status(u16, &'a HeaderMap),
+    Other,
+}
+
+impl RetryPolicy {
+    /// 是否可以重试该方法的请求：非幂等请求可能已被服务器处理，只在显式允许时重试
+    pub fn applies_to(&self, method: &str) -> bool {
+        self.retry_non_idempotent
+            || reqwest::Method::from_bytes(method.to_uppercase().as_bytes()).is_ok_and(|method| method.is_idempotent())
+    }
+
+    /// 第 `attempt` 次尝试失败后，返回下一次尝试前的等待时间；不应重试，
+    /// 或需要等待的时间超出上限或剩余的总超时 `remaining` 时返回 None
+    pub fn next_delay(&self, attempt: u32, failure: RetryableFailure, remaining: Option<Duration>) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+
+        let retry_after = match failure {
+            RetryableFailure::Connect if self.retry_on_connect_error => None,
+            RetryableFailure::Timeout if self.retry_on_timeout => None,
+            RetryableFailure::Status(status, headers) if self.retry_status_codes.contains(&status) => {
+                if self.respect_retry_after {
+                    parse_retry_after(headers)
+                } else {
+                    None
+                }
+            }
+            _ => return None,
+        };
+
+        let delay = match retry_after {
+            // 不提前于服务端要求的时间重试，等待过久时放弃
+            Some(retry_after) if retry_after > Duration::from_millis(self.max_delay) => return None,
+            Some(retry_after) => retry_after,
+            None => Duration::from_millis(self.backoff(attempt)),
+        };
+        remaining.is_none_or(|remaining| delay < remaining).then_some(delay)
+    }
+
+    // 指数退避：initial_delay * 2^(attempt - 1)
+    fn backoff(&self, attempt: u32) -> u64 {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let backoff = self.initial_delay.saturating_mul(1u64 << exponent).min(self.max_delay);
+        if self.jitter && backoff > 0 {
+            // 等待时间在 [backoff / 2, backoff] 之间随机
+            backoff / 2 + rand::random_range(0..=backoff - backoff / 2)
+        } else {
+            backoff
+        }
+    }
+}
+
+/// 解析 Retry-After 头，支持秒数和 HTTP 日期两种形式
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let millis = date.timestamp_millis() - chrono::Utc::now().timestamp_millis();
+    Some(Duration::from_millis(millis.max(0) as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 10,
+            initial_delay: 100,
+            max_delay: 1000,
+            jitter: false,
+            ..Default::default()
+        }
+    }
+
+    fn retry_after(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max_delay() {
+        let policy = policy();
+        let delays: Vec<_> = (1..=6)
+            .map(|attempt| policy.next_delay(attempt, RetryableFailure::Connect, None).unwrap().as_millis())
+            .collect();
+        assert_eq!(delays, [100, 200, 400, 800, 1000, 1000]);
+        assert_eq!(policy.next_delay(10, RetryableFailure::Connect, None), None);
+    }
+
+    #[test]
+    fn jitter_stays_within_upper_half_of_backoff() {
+        let policy = RetryPolicy { jitter: true, ..policy() };
+        for _ in 0..200 {
+            let delay = policy.next_delay(3, RetryableFailure::Timeout, None).unwrap().as_millis();
+            assert!((200..=400).contains(&delay), "delay {} out of range", delay);
+        }
+    }
+
+    #[test]
+    fn retries_only_configured_failures() {
+        let headers = HeaderMap::new();
+        let policy = RetryPolicy { retry_on_timeout: false, ..policy() };
+        assert!(policy.next_delay(1, RetryableFailure::Status(503, &headers), None).is_some());
+        assert_eq!(policy.next_delay(1, RetryableFailure::Status(500, &headers), None), None);
+        assert_eq!(policy.next_delay(1, RetryableFailure::Timeout, None), None);
+        assert_eq!(policy.next_delay(1, RetryableFailure::Other, None), None);
+    }
+
+    #[test]
+    fn honors_retry_after_within_limits() {
+        let policy = policy();
+        let headers = retry_after("1");
+        let failure = RetryableFailure::Status(429, &headers);
+        assert_eq!(policy.next_delay(1, failure, None), Some(Duration::from_secs(1)));
+        // 不超过上限也不能超出剩余的总超时
+        assert_eq!(policy.next_delay(1, failure, Some(Duration::from_millis(500))), None);
+        let headers = retry_after("120");
+        assert_eq!(policy.next_delay(1, RetryableFailure::Status(429, &headers), None), None);
+
+        let policy = RetryPolicy { respect_retry_after: false, ..policy };
+        assert_eq!(policy.next_delay(1, RetryableFailure::Status(429, &headers), None), Some(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn parses_retry_after_forms() {
+        assert_eq!(parse_retry_after(&retry_after("30")), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after(&retry_after(" 0 ")), Some(Duration::ZERO));
+
+        let date = (chrono::Utc::now() + chrono::Duration::seconds(60)).format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        let delay = parse_retry_after(&retry_after(&date)).unwrap();
+        assert!(delay > Duration::from_secs(55) && delay <= Duration::from_secs(60));
+        // 已经过去的日期表示立即重试
+        assert_eq!(parse_retry_after(&retry_after("Sun, 06 Nov 1994 08:49:37 GMT")), Some(Duration::ZERO));
+
+        assert_eq!(parse_retry_after(&retry_after("soon")), None);
+        assert_eq!(parse_retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn non_idempotent_methods_need_opt_in() {
+        let policy = policy();
+        for method in ["GET", "head", "PUT", "DELETE", "OPTIONS"] {
+            assert!(policy.applies_to(method), "{}", method);
+        }
+        assert!(!policy.applies_to("POST"));
+        assert!(!policy.applies_to("patch"));
+
+        let policy = RetryPolicy { retry_non_idempotent: true, ..policy };
+        assert!(policy.applies_to("POST"));
+        assert!(policy.applies_to("PATCH"));
+    }
+}
@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+
+// 参与判断的最大字节数
+const SNIFF_BYTES: usize = 4096;
+
+/// 建议的响应体查看方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Viewer {
+    Json,
+    Xml,
+    Html,
+    Text,
+    Image,
+    Pdf,
+    Audio,
+    Video,
+    /// 无法直接显示的二进制内容
+    Hex,
+}
+
+/// 响应体的实际类型
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentInfo {
+    /// 不含参数的 MIME 类型
+    pub mime_type: String,
+    pub viewer: Viewer,
+    /// 类型由内容推断，而不是来自 Content-Type
+    pub sniffed: bool,
+}
+
+fn mime_type(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase()
+}
+
+// 这些类型不说明实际内容，需要按内容判断
+fn is_unknown(mime: &str) -> bool {
+    matches!(
+        mime,
+        "" | "application/octet-stream" | "binary/octet-stream" | "application/unknown" | "unknown/unknown" | "*/*"
+    )
+}
+
+/// 按 MIME 类型选择查看方式
+fn viewer_for(mime: &str) -> Viewer {
+    let (kind, subtype) = mime.split_once('/').unwrap_or((mime, ""));
+    match (kind, subtype) {
+        (_, "json") | (_, "x-ndjson") => Viewer::Json,
+        // CBOR 和 MessagePack 会转换为 JSON 显示
+        (_, "cbor") | (_, "msgpack") | (_, "x-msgpack") | (_, "vnd.msgpack") => Viewer::Json,
+        (_, subtype) if subtype.ends_with("+json") => Viewer::Json,
+        (_, "html") | (_, "xhtml+xml") => Viewer::Html,
+        ("image", _) => Viewer::Image,
+        (_, "xml") => Viewer::Xml,
+        (_, subtype) if subtype.ends_with("+xml") => Viewer::Xml,
+        (_, "pdf") => Viewer::Pdf,
+        ("audio", _) => Viewer::Audio,
+        ("video", _) => Viewer::Video,
+        ("text", _) => Viewer::Text,
+        (_, "javascript") | (_, "x-javascript") | (_, "ecmascript") | (_, "x-www-form-urlencoded") | (_, "graphql")
+        | (_, "yaml") | (_, "x-yaml") => Viewer::Text,
+        _ => Viewer::Hex,
+    }
+}
+
+/// 按文件头识别的二进制格式
+fn sniff_magic(body: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"\x00\x00\x01\x00", "image/x-icon"),
+        (b"%PDF-", "application/pdf"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\x28\xb5\x2f\xfd", "application/zstd"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"OggS", "audio/ogg"),
+        (b"ID3", "audio/mpeg"),
+        (b"fLaC", "audio/flac"),
+        (b"\x1a\x45\xdf\xa3", "video/webm"),
+        (b"\x00asm", "application/wasm"),
+        // CBOR 自描述标签 55799
+        (b"\xd9\xd9\xf7", "application/cbor"),
+    ];
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(signature, _)| body.starts_with(signature)) {
+        return Some(mime);
+    }
+
+    // BMP 的文件头只有两个字节，再检查保留字段
+    if body.starts_with(b"BM") && body.get(6..10) == Some(&[0, 0, 0, 0]) {
+        return Some("image/bmp");
+    }
+
+    // RIFF 容器和 ISO BMFF 的类型标记不在文件开头
+    match (body.get(0..4), body.get(8..12)) {
+        (Some(b"RIFF"), Some(b"WEBP")) => return Some("image/webp"),
+        (Some(b"RIFF"), Some(b"WAVE")) => return Some("audio/wav"),
+        (Some(b"RIFF"), Some(b"AVI ")) => return Some("video/x-msvideo"),
+        _ => {}
+    }
+    match (body.get(4..8), body.get(8..12)) {
+        (Some(b"ftyp"), Some(b"avif")) => Some("image/avif"),
+        (Some(b"ftyp"), Some(b"heic")) => Some("image/heic"),
+        (Some(b"ftyp"), Some(b"qt  ")) => Some("video/quicktime"),
+        (Some(b"ftyp"), Some(_)) => Some("video/mp4"),
+        _ => None,
+    }
+}
+
+fn looks_like_json(text: &[u8], partial: bool) -> bool {
+    if !matches!(text.first(), Some(b'{' | b'[')) {
+        return false;
+    }
+    if serde_json::from_slice::<serde::de::IgnoredAny>(text).is_ok() {
+        return true;
+    }
+    // 截断的内容无法完整解析，只检查开头
+    partial
+        && matches!(
+            text[1..].trim_ascii_start().first(),
+            None | Some(b'"' | b'{' | b'[' | b']' | b'}' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n')
+        )
+}
+
+fn sniff_text(body: &[u8], partial: bool) -> Option<&'static str> {
+    let body = body.strip_prefix(b"\xef\xbb\xbf").unwrap_or(body);
+    let text = body.trim_ascii();
+    let head = text[..text.len().min(SNIFF_BYTES)].to_ascii_lowercase();
+
+    if looks_like_json(text, partial) {
+        return Some("application/json");
+    }
+    if head.starts_with(b"<?xml") {
+        let is_svg = head.windows(4).any(|window| window == b"<svg");
+        return Some(if is_svg { "image/svg+xml" } else { "application/xml" });
+    }
+    if head.starts_with(b"<svg") {
+        return Some("image/svg+xml");
+    }
+    const HTML_PREFIXES: &[&[u8]] = &[b"<!doctype html", b"<html", b"<head", b"<body", b"<!--", b"<div", b"<p>"];
+    if HTML_PREFIXES.iter().any(|prefix| head.starts_with(prefix)) {
+        return Some("text/html");
+    }
+
+    // 没有控制字符（换行、制表符等除外）的 UTF-8 视为纯文本
+    let sample = &body[..body.len().min(SNIFF_BYTES)];
+    let valid = match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    let has_control = sample
+        .iter()
+        .any(|&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b));
+    (valid && !has_control).then_some("text/plain")
+}
+
+fn read_varint(data: &[u8], position: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*position)?;
+        *position += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// 整段内容都能按 protobuf 线格式解析时视为 protobuf
+fn looks_like_protobuf(body: &[u8], partial: bool) -> bool {
+    let mut position = 0;
+    let mut fields = 0;
+    while position < body.len() {
+        let Some(key) = read_varint(body, &mut position) else {
+            return partial && fields > 0;
+        };
+        if key >> 3 == 0 {
+            return false;
+        }
+        let skip = match key & 0x7 {
+            0 => match read_varint(body, &mut position) {
+                Some(_) => 0,
+                None => return partial && fields > 0,
+            },
+            1 => 8,
+            2 => match read_varint(body, &mut position) {
+                Some(length) => length,
+                None => return partial && fields > 0,
+            },
+            5 => 4,
+            _ => return false,
+        };
+        position = position.saturating_add(usize::try_from(skip).unwrap_or(usize::MAX));
+        if position > body.len() {
+            return partial && fields > 0;
+        }
+        fields += 1;
+    }
+    fields > 0
+}
+
+/// 确定响应体类型：Content-Type 缺失或为 application/octet-stream 等泛化类型时按内容推断，
+/// `partial` 表示 body 只是完整内容的开头
+pub fn detect(content_type: Option<&str>, body: &[u8], partial: bool) -> ContentInfo {
+    let declared = content_type.map(mime_type).unwrap_or_default();
+    if !is_unknown(&declared) {
+        return ContentInfo {
+            viewer: viewer_for(&declared),
+            mime_type: declared,
+            sniffed: false,
+        };
+    }
+    if body.is_empty() {
+        let mime_type = if declared.is_empty() { "application/octet-stream".to_string() } else { declared };
+        return ContentInfo { mime_type, viewer: Viewer::Hex, sniffed: false };
+    }
+
+    let mime = sniff_magic(body)
+        .or_else(|| sniff_text(body, partial))
+        .or_else(|| looks_like_protobuf(body, partial).then_some("application/x-protobuf"))
+        .unwrap_or("application/octet-stream");
+    ContentInfo {
+        mime_type: mime.to_string(),
+        viewer: viewer_for(mime),
+        sniffed: true,
+    }
+}
@@ -0,0 +1,1037 @@
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::http_client::{client_config_or_global, timeout_duration, ClientConfig};
+use crate::retry::{RetryPolicy, RetryableFailure};
+use crate::ws::{self, WsStream};
+
+/// Socket.IO 事件名
+pub const SOCKETIO_EVENT: &str = "socketio-event";
+
+// 未配置超时时等待握手和 ack 的时间
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(20);
+
+// Socket.IO 包类型
+const CONNECT: u8 = 0;
+const DISCONNECT: u8 = 1;
+const EVENT: u8 = 2;
+const ACK: u8 = 3;
+const CONNECT_ERROR: u8 = 4;
+const BINARY_EVENT: u8 = 5;
+const BINARY_ACK: u8 = 6;
+
+struct ConnectionHandle {
+    commands: mpsc::UnboundedSender<Command>,
+    timeout: Duration,
+    /// 区分先后使用同一 connection_id 的连接
+    generation: u64,
+}
+
+// 已建立和正在建立的连接，按 connection_id 索引
+static CONNECTIONS: OnceLock<Mutex<HashMap<String, ConnectionHandle>>> = OnceLock::new();
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+fn get_connections() -> &'static Mutex<HashMap<String, ConnectionHandle>> {
+    CONNECTIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 登记连接并占用 connection_id，ID 已被占用时返回错误
+fn register(connection_id: &str, commands: mpsc::UnboundedSender<Command>, timeout: Duration) -> Result<u64, String> {
+    let mut connections = get_connections().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+    if connections.contains_key(connection_id) {
+        return Err(format!("Socket.IO connection {} is already open", connection_id));
+    }
+    let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+    connections.insert(connection_id.to_string(), ConnectionHandle { commands, timeout, generation });
+    Ok(generation)
+}
+
+/// 只在登记的仍是这个连接时移除，不影响之后使用同一 ID 的连接
+fn unregister(connection_id: &str, generation: u64) {
+    if let Ok(mut connections) = get_connections().lock()
+        && connections.get(connection_id).is_some_and(|handle| handle.generation == generation)
+    {
+        connections.remove(connection_id);
+    }
+}
+
+/// 断线重连策略，延迟按指数退避增长
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReconnectionOptions {
+    pub enabled: bool,
+    /// 最大重连次数，0 表示不限制
+    pub max_attempts: u32,
+    /// 首次重连前的等待时间（毫秒）
+    pub delay: u64,
+    /// 单次等待的上限（毫秒）
+    pub max_delay: u64,
+}
+
+impl Default for ReconnectionOptions {
+    fn default() -> Self {
+        ReconnectionOptions {
+            enabled: true,
+            max_attempts: 0,
+            delay: 1000,
+            max_delay: 5000,
+        }
+    }
+}
+
+impl ReconnectionOptions {
+    /// 第 `attempt` 次重连前的等待时间，超过最大次数时返回 None
+    fn delay(&self, attempt: u32) -> Option<Duration> {
+        let policy = RetryPolicy {
+            max_attempts: if self.max_attempts == 0 { u32::MAX } else { self.max_attempts.saturating_add(1) },
+            initial_delay: self.delay,
+            max_delay: self.max_delay,
+            ..RetryPolicy::default()
+        };
+        policy.next_delay(attempt, RetryableFailure::Connect, None)
+    }
+}
+
+/// Socket.IO 连接
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SocketIoConnectRequest {
+    /// 连接 ID，用于关联事件和后续操作
+    pub connection_id: String,
+    /// 服务地址，路径部分作为默认命名空间，如 `https://example.com/chat`
+    pub url: String,
+    /// Engine.IO 路径，默认为 `/socket.io/`
+    pub path: Option<String>,
+    /// 要连接的命名空间，未指定时使用 URL 中的命名空间
+    #[serde(default)]
+    pub namespaces: Vec<String>,
+    /// 连接命名空间时发送的认证数据
+    pub auth: Option<Json>,
+    /// 握手 URL 的查询参数
+    #[serde(default)]
+    pub query: HashMap<String, String>,
+    /// 握手请求的请求头
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub reconnection: ReconnectionOptions,
+    /// 未设置时使用全局配置
+    pub config: Option<ClientConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SocketIoNamespace {
+    pub namespace: String,
+    pub sid: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SocketIoSession {
+    /// Engine.IO 会话 ID
+    pub sid: String,
+    pub namespaces: Vec<SocketIoNamespace>,
+}
+
+/// Socket.IO 事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SocketIoEventKind {
+    /// 命名空间已连接（含重连后）
+    Connect,
+    /// 服务端拒绝连接命名空间
+    ConnectError,
+    /// 命名空间断开，reason 与 socket.io-client 一致
+    Disconnect,
+    /// 开始第 attempt 次重连
+    Reconnecting,
+    /// 重连次数用尽
+    ReconnectFailed,
+    /// 服务端发来的事件，ack_id 不为空时服务端在等待确认
+    Event,
+    /// 连接已关闭，之后不会再有事件
+    Closed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SocketIoEvent {
+    pub connection_id: String,
+    pub kind: SocketIoEventKind,
+    pub namespace: Option<String>,
+    pub event: Option<String>,
+    pub args: Vec<Json>,
+    pub ack_id: Option<u64>,
+    pub reason: Option<String>,
+    pub attempt: Option<u32>,
+}
+
+// emit 的结果，等待确认时为服务端返回的确认参数
+type AckReply = oneshot::Sender<Result<Vec<Json>, String>>;
+
+enum Command {
+    Emit {
+        namespace: String,
+        event: String,
+        args: Vec<Json>,
+        with_ack: bool,
+        reply: AckReply,
+    },
+    Ack {
+        namespace: String,
+        id: u64,
+        args: Vec<Json>,
+    },
+    Join {
+        namespace: String,
+        auth: Option<Json>,
+        reply: oneshot::Sender<Result<String, String>>,
+    },
+    Leave {
+        namespace: String,
+    },
+    Disconnect,
+}
+
+/// 解析后的 Socket.IO 包
+#[derive(Debug)]
+struct Packet {
+    kind: u8,
+    attachments: usize,
+    namespace: String,
+    id: Option<u64>,
+    data: Option<Json>,
+}
+
+/// 解析 Socket.IO 包：`<类型>[<附件数>-][<命名空间>,][<ack id>][JSON]`
+fn parse_packet(text: &str) -> Result<Packet, String> {
+    let invalid = || format!("Invalid Socket.IO packet: {}", text);
+    let kind = text
+        .chars()
+        .next()
+        .and_then(|c| c.to_digit(10))
+        .filter(|&kind| kind <= u32::from(BINARY_ACK))
+        .ok_or_else(invalid)? as u8;
+    let mut rest = &text[1..];
+
+    let mut attachments = 0;
+    if kind == BINARY_EVENT || kind == BINARY_ACK {
+        let (count, after) = rest.split_once('-').ok_or_else(invalid)?;
+        attachments = count.parse().map_err(|_| invalid())?;
+        rest = after;
+    }
+
+    let mut namespace = "/".to_string();
+    if rest.starts_with('/') {
+        let end = rest.find(',').unwrap_or(rest.len());
+        namespace = rest[..end].to_string();
+        rest = rest.get(end + 1..).unwrap_or("");
+    }
+
+    let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    let id = if digits > 0 { Some(rest[..digits].parse().map_err(|_| invalid())?) } else { None };
+    rest = &rest[digits..];
+
+    let data = if rest.is_empty() { None } else { Some(serde_json::from_str(rest).map_err(|_| invalid())?) };
+    Ok(Packet { kind, attachments, namespace, id, data })
+}
+
+/// 编码为 Engine.IO message（`4`）包
+fn encode_packet(kind: u8, namespace: &str, id: Option<u64>, data: Option<&Json>) -> String {
+    let mut packet = format!("4{}", kind);
+    if namespace != "/" {
+        packet.push_str(namespace);
+        packet.push(',');
+    }
+    if let Some(id) = id {
+        packet.push_str(&id.to_string());
+    }
+    if let Some(data) = data {
+        packet.push_str(&data.to_string());
+    }
+    packet
+}
+
+/// 把二进制附件的占位符替换为 base64 字符串
+fn fill_placeholders(value: &mut Json, attachments: &[Vec<u8>]) {
+    let placeholder = value
+        .as_object()
+        .filter(|object| object.get("_placeholder") == Some(&Json::Bool(true)))
+        .and_then(|object| object.get("num"))
+        .and_then(Json::as_u64)
+        .and_then(|num| attachments.get(num as usize));
+    if let Some(data) = placeholder {
+        *value = Json::String(BASE64.encode(data));
+        return;
+    }
+    match value {
+        Json::Array(items) => items.iter_mut().for_each(|item| fill_placeholders(item, attachments)),
+        Json::Object(entries) => entries.values_mut().for_each(|item| fill_placeholders(item, attachments)),
+        _ => {}
+    }
+}
+
+fn normalize_namespace(namespace: &str) -> String {
+    let namespace = namespace.trim().trim_end_matches('/');
+    if namespace.is_empty() {
+        "/".to_string()
+    } else if namespace.starts_with('/') {
+        namespace.to_string()
+    } else {
+        format!("/{}", namespace)
+    }
+}
+
+/// 构造 Engine.IO 握手地址，返回地址和 URL 中的命名空间
+fn engine_url(url: &str, path: Option<&str>, query: &HashMap<String, String>) -> Result<(String, String), String> {
+    let mut parsed_url = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+    let namespace = normalize_namespace(parsed_url.path());
+
+    let path = path.map(str::trim).filter(|path| !path.is_empty()).unwrap_or("/socket.io/");
+    let path = format!("/{}/", path.trim_matches('/'));
+    parsed_url.set_path(&path);
+    {
+        let mut pairs = parsed_url.query_pairs_mut();
+        for (key, value) in query {
+            pairs.append_pair(key, value);
+        }
+        pairs.append_pair("EIO", "4").append_pair("transport", "websocket");
+    }
+    Ok((parsed_url.to_string(), namespace))
+}
+
+/// 一次 Engine.IO 会话（断线重连后会换成新的）
+struct Engine {
+    stream: WsStream,
+    sid: String,
+    /// 两次 ping 之间允许的最长间隔（pingInterval + pingTimeout）
+    ping_timeout: Duration,
+    last_ping: Instant,
+}
+
+struct Namespace {
+    name: String,
+    auth: Option<Json>,
+    sid: Option<String>,
+    /// 正在等待服务端确认连接
+    connecting: bool,
+    join_reply: Option<oneshot::Sender<Result<String, String>>>,
+}
+
+// 一次会话结束的原因
+enum SessionEnd {
+    /// 主动断开
+    Closed,
+    /// 连接丢失，需要重连
+    Lost(String),
+}
+
+struct Connection {
+    app: AppHandle,
+    id: String,
+    generation: u64,
+    engine_url: String,
+    headers: HashMap<String, String>,
+    client_config: ClientConfig,
+    reconnection: ReconnectionOptions,
+    timeout: Duration,
+    namespaces: Vec<Namespace>,
+    /// 等待确认的 emit 及其超时时间
+    acks: HashMap<u64, (Instant, AckReply)>,
+    next_ack_id: u64,
+    /// 等待二进制附件的包
+    binary: Option<(Packet, Vec<Vec<u8>>)>,
+    /// 重连期间收到的操作
+    queued: VecDeque<Command>,
+}
+
+impl Connection {
+    fn emit(&self, kind: SocketIoEventKind, namespace: Option<&str>, update: impl FnOnce(&mut SocketIoEvent)) {
+        let mut event = SocketIoEvent {
+            connection_id: self.id.clone(),
+            kind,
+            namespace: namespace.map(str::to_string),
+            event: None,
+            args: Vec::new(),
+            ack_id: None,
+            reason: None,
+            attempt: None,
+        };
+        update(&mut event);
+        if let Err(e) = self.app.emit(SOCKETIO_EVENT, event) {
+            log::warn!("Failed to emit {}: {}", SOCKETIO_EVENT, e);
+        }
+    }
+
+    fn namespace_mut(&mut self, name: &str) -> Option<&mut Namespace> {
+        self.namespaces.iter_mut().find(|namespace| namespace.name == name)
+    }
+
+    /// 建立 WebSocket 并等待 Engine.IO open 包
+    async fn open_engine(&self) -> Result<Engine, String> {
+        let mut stream = ws::connect(&self.engine_url, &self.headers, &[], &self.client_config).await?.stream;
+        let wait_open = async {
+            while let Some(frame) = stream.next().await {
+                match frame.map_err(|e| format!("Connection failed: {}", e))? {
+                    Message::Text(text) if text.starts_with('0') => {
+                        let open: Json =
+                            serde_json::from_str(&text[1..]).map_err(|e| format!("Invalid handshake: {}", e))?;
+                        return Ok(open);
+                    }
+                    Message::Close(_) => break,
+                    _ => {}
+                }
+            }
+            Err("Server closed the connection during handshake".to_string())
+        };
+        let open = tokio::time::timeout(self.timeout, wait_open)
+            .await
+            .map_err(|_| "Timed out waiting for Engine.IO handshake".to_string())??;
+
+        let interval = open.get("pingInterval").and_then(Json::as_u64).unwrap_or(25000);
+        let timeout = open.get("pingTimeout").and_then(Json::as_u64).unwrap_or(20000);
+        Ok(Engine {
+            stream,
+            sid: open.get("sid").and_then(Json::as_str).unwrap_or_default().to_string(),
+            ping_timeout: Duration::from_millis(interval + timeout),
+            last_ping: Instant::now(),
+        })
+    }
+
+    async fn send(&self, engine: &mut Engine, packet: String) -> Result<(), String> {
+        engine
+            .stream
+            .send(Message::text(packet))
+            .await
+            .map_err(|e| format!("Failed to send packet: {}", e))
+    }
+
+    /// 连接全部命名空间并等待服务端响应，返回被拒绝的命名空间及原因
+    async fn connect_namespaces(&mut self, engine: &mut Engine) -> Result<Vec<(String, String)>, String> {
+        let mut packets = Vec::new();
+        for namespace in &mut self.namespaces {
+            namespace.sid = None;
+            namespace.connecting = true;
+            packets.push(encode_packet(CONNECT, &namespace.name, None, namespace.auth.as_ref()));
+        }
+        for packet in packets {
+            self.send(engine, packet).await?;
+        }
+
+        let mut rejected = Vec::new();
+        let timeout = self.timeout;
+        let wait_connected = async {
+            while self.namespaces.iter().any(|namespace| namespace.connecting) {
+                let frame = engine
+                    .stream
+                    .next()
+                    .await
+                    .ok_or("Server closed the connection")?
+                    .map_err(|e| format!("Connection failed: {}", e))?;
+                if let Some(packet) = self.read_frame(engine, frame).await? {
+                    if packet.kind == CONNECT_ERROR {
+                        rejected.push((packet.namespace.clone(), error_message(packet.data.as_ref())));
+                    }
+                    self.dispatch(packet);
+                }
+            }
+            Ok::<_, String>(())
+        };
+        tokio::time::timeout(timeout, wait_connected)
+            .await
+            .map_err(|_| "Timed out waiting for namespace connection".to_string())??;
+        Ok(rejected)
+    }
+
+    /// 处理一个 WebSocket 帧，返回完整的 Socket.IO 包
+    async fn read_frame(&mut self, engine: &mut Engine, frame: Message) -> Result<Option<Packet>, String> {
+        match frame {
+            Message::Text(text) => match text.chars().next() {
+                // ping，回复 pong
+                Some('2') => {
+                    engine.last_ping = Instant::now();
+                    self.send(engine, format!("3{}", &text[1..])).await?;
+                    Ok(None)
+                }
+                Some('4') => {
+                    let packet = parse_packet(&text[1..])?;
+                    if packet.attachments > 0 {
+                        self.binary = Some((packet, Vec::new()));
+                        return Ok(None);
+                    }
+                    Ok(Some(packet))
+                }
+                Some('1') => Err("transport close".to_string()),
+                _ => Ok(None),
+            },
+            Message::Binary(data) => {
+                let Some((packet, mut attachments)) = self.binary.take() else {
+                    return Ok(None);
+                };
+                attachments.push(data.to_vec());
+                if attachments.len() < packet.attachments {
+                    self.binary = Some((packet, attachments));
+                    return Ok(None);
+                }
+                let mut packet = packet;
+                if let Some(data) = &mut packet.data {
+                    fill_placeholders(data, &attachments);
+                }
+                Ok(Some(packet))
+            }
+            Message::Close(_) => Err("transport close".to_string()),
+            _ => Ok(None),
+        }
+    }
+
+    fn dispatch(&mut self, packet: Packet) {
+        let namespace = packet.namespace.as_str();
+        match packet.kind {
+            CONNECT => {
+                let sid = packet
+                    .data
+                    .as_ref()
+                    .and_then(|data| data.get("sid"))
+                    .and_then(Json::as_str)
+                    .unwrap_or_default()
+                    .to_string();
+                if let Some(state) = self.namespace_mut(namespace) {
+                    state.sid = Some(sid.clone());
+                    state.connecting = false;
+                    if let Some(reply) = state.join_reply.take() {
+                        let _ = reply.send(Ok(sid));
+                    }
+                    self.emit(SocketIoEventKind::Connect, Some(namespace), |_| {});
+                }
+            }
+            CONNECT_ERROR => {
+                let message = error_message(packet.data.as_ref());
+                if let Some(index) = self.namespaces.iter().position(|state| state.name == namespace) {
+                    let state = self.namespaces.remove(index);
+                    if let Some(reply) = state.join_reply {
+                        let _ = reply.send(Err(message.clone()));
+                    }
+                }
+                self.emit(SocketIoEventKind::ConnectError, Some(namespace), |event| event.reason = Some(message));
+            }
+            DISCONNECT => {
+                // 服务端主动断开的命名空间不会自动重连
+                self.namespaces.retain(|state| state.name != namespace);
+                self.emit(SocketIoEventKind::Disconnect, Some(namespace), |event| {
+                    event.reason = Some("io server disconnect".to_string())
+                });
+            }
+            EVENT | BINARY_EVENT => {
+                let mut args = match packet.data {
+                    Some(Json::Array(args)) => args,
+                    _ => return,
+                };
+                if args.is_empty() {
+                    return;
+                }
+                let name = match args.remove(0) {
+                    Json::String(name) => name,
+                    name => name.to_string(),
+                };
+                self.emit(SocketIoEventKind::Event, Some(namespace), |event| {
+                    event.event = Some(name);
+                    event.args = args;
+                    event.ack_id = packet.id;
+                });
+            }
+            ACK | BINARY_ACK => {
+                if let Some((_, reply)) = packet.id.and_then(|id| self.acks.remove(&id)) {
+                    let args = match packet.data {
+                        Some(Json::Array(args)) => args,
+                        Some(data) => vec![data],
+                        None => Vec::new(),
+                    };
+                    let _ = reply.send(Ok(args));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 执行一个操作，返回是否要关闭连接
+    async fn execute(&mut self, engine: &mut Engine, command: Command) -> Result<bool, String> {
+        match command {
+            Command::Emit { namespace, event, args, with_ack, reply } => {
+                if !self.namespaces.iter().any(|state| state.name == namespace && state.sid.is_some()) {
+                    let _ = reply.send(Err(format!("Namespace {} is not connected", namespace)));
+                    return Ok(false);
+                }
+                let id = with_ack.then(|| {
+                    self.next_ack_id += 1;
+                    self.next_ack_id
+                });
+                let mut data = vec![Json::String(event)];
+                data.extend(args);
+                if let Err(e) = self.send(engine, encode_packet(EVENT, &namespace, id, Some(&Json::Array(data)))).await {
+                    let _ = reply.send(Err(e.clone()));
+                    return Err(e);
+                }
+                match id {
+                    Some(id) => {
+                        self.acks.insert(id, (Instant::now() + self.timeout, reply));
+                    }
+                    None => {
+                        let _ = reply.send(Ok(Vec::new()));
+                    }
+                }
+            }
+            Command::Ack { namespace, id, args } => {
+                self.send(engine, encode_packet(ACK, &namespace, Some(id), Some(&Json::Array(args)))).await?;
+            }
+            Command::Join { namespace, auth, reply } => {
+                if let Some(sid) = self.namespace_mut(&namespace).and_then(|state| state.sid.clone()) {
+                    let _ = reply.send(Ok(sid));
+                    return Ok(false);
+                }
+                self.namespaces.retain(|state| state.name != namespace);
+                let packet = encode_packet(CONNECT, &namespace, None, auth.as_ref());
+                self.namespaces.push(Namespace {
+                    name: namespace,
+                    auth,
+                    sid: None,
+                    connecting: true,
+                    join_reply: Some(reply),
+                });
+                self.send(engine, packet).await?;
+            }
+            Command::Leave { namespace } => {
+                if let Some(index) = self.namespaces.iter().position(|state| state.name == namespace) {
+                    self.namespaces.remove(index);
+                    self.send(engine, encode_packet(DISCONNECT, &namespace, None, None)).await?;
+                    self.emit(SocketIoEventKind::Disconnect, Some(&namespace), |event| {
+                        event.reason = Some("io client disconnect".to_string())
+                    });
+                }
+            }
+            Command::Disconnect => {
+                let names: Vec<String> = self.namespaces.iter().map(|state| state.name.clone()).collect();
+                for name in names {
+                    let _ = self.send(engine, encode_packet(DISCONNECT, &name, None, None)).await;
+                    self.emit(SocketIoEventKind::Disconnect, Some(&name), |event| {
+                        event.reason = Some("io client disconnect".to_string())
+                    });
+                }
+                let _ = engine.stream.close(None).await;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// 超时未收到确认的 emit 不再等待
+    fn expire_acks(&mut self) {
+        let now = Instant::now();
+        let expired: Vec<u64> = self
+            .acks
+            .iter()
+            .filter(|(_, (deadline, _))| *deadline <= now)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in expired {
+            if let Some((_, reply)) = self.acks.remove(&id) {
+                let _ = reply.send(Err("Timed out waiting for acknowledgement".to_string()));
+            }
+        }
+    }
+
+    /// 处理消息和操作，直到连接丢失或主动断开
+    async fn serve(&mut self, engine: &mut Engine, commands: &mut mpsc::UnboundedReceiver<Command>) -> SessionEnd {
+        while let Some(command) = self.queued.pop_front() {
+            match self.execute(engine, command).await {
+                Ok(false) => {}
+                Ok(true) => return SessionEnd::Closed,
+                Err(e) => return SessionEnd::Lost(e),
+            }
+        }
+
+        loop {
+            let ping_deadline = engine.last_ping + engine.ping_timeout;
+            let ack_deadline = self.acks.values().map(|(deadline, _)| *deadline).min();
+            tokio::select! {
+                command = commands.recv() => {
+                    let Some(command) = command else {
+                        return SessionEnd::Closed;
+                    };
+                    match self.execute(engine, command).await {
+                        Ok(false) => {}
+                        Ok(true) => return SessionEnd::Closed,
+                        Err(e) => return SessionEnd::Lost(e),
+                    }
+                }
+                frame = engine.stream.next() => {
+                    let frame = match frame {
+                        Some(Ok(frame)) => frame,
+                        Some(Err(e)) => return SessionEnd::Lost(format!("transport error: {}", e)),
+                        None => return SessionEnd::Lost("transport close".to_string()),
+                    };
+                    match self.read_frame(engine, frame).await {
+                        Ok(Some(packet)) => self.dispatch(packet),
+                        Ok(None) => {}
+                        Err(e) => return SessionEnd::Lost(e),
+                    }
+                }
+                _ = tokio::time::sleep_until(ping_deadline) => {
+                    return SessionEnd::Lost("ping timeout".to_string());
+                }
+                _ = tokio::time::sleep_until(ack_deadline.unwrap_or(ping_deadline)), if ack_deadline.is_some() => {
+                    self.expire_acks();
+                }
+            }
+        }
+    }
+
+    /// 按重连策略重新建立会话，重连次数用尽或主动断开时返回 None
+    async fn reconnect(&mut self, commands: &mut mpsc::UnboundedReceiver<Command>) -> Option<Engine> {
+        let mut attempt = 1;
+        loop {
+            let Some(delay) = self.reconnection.delay(attempt) else {
+                self.emit(SocketIoEventKind::ReconnectFailed, None, |event| event.attempt = Some(attempt - 1));
+                return None;
+            };
+            // 等待期间仍然响应断开操作，其他操作留到重连后执行
+            let sleep = tokio::time::sleep(delay);
+            tokio::pin!(sleep);
+            loop {
+                tokio::select! {
+                    _ = &mut sleep => break,
+                    command = commands.recv() => match command {
+                        None | Some(Command::Disconnect) => return None,
+                        Some(command) => self.queued.push_back(command),
+                    },
+                }
+            }
+
+            self.emit(SocketIoEventKind::Reconnecting, None, |event| event.attempt = Some(attempt));
+            let result = match self.open_engine().await {
+                Ok(mut engine) => self.connect_namespaces(&mut engine).await.map(|_| engine),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(engine) => return Some(engine),
+                Err(e) => {
+                    log::debug!("Socket.IO reconnect attempt {} failed: {}", attempt, e);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// 建立第一次会话，任一命名空间被拒绝时失败
+    async fn open_session(&mut self) -> Result<Engine, String> {
+        let mut engine = self.open_engine().await?;
+        let rejected = self.connect_namespaces(&mut engine).await?;
+        if let Some((namespace, message)) = rejected.into_iter().next() {
+            let _ = engine.stream.close(None).await;
+            return Err(format!("Failed to connect to namespace {}: {}", namespace, message));
+        }
+        Ok(engine)
+    }
+
+    fn connection_lost(&mut self, reason: &str) {
+        for state in &mut self.namespaces {
+            state.sid = None;
+        }
+        let names: Vec<String> = self.namespaces.iter().map(|state| state.name.clone()).collect();
+        for name in names {
+            self.emit(SocketIoEventKind::Disconnect, Some(&name), |event| event.reason = Some(reason.to_string()));
+        }
+        // 未收到确认的 emit 不会再有结果
+        for (_, (_, reply)) in self.acks.drain() {
+            let _ = reply.send(Err(format!("Connection lost: {}", reason)));
+        }
+        self.binary = None;
+    }
+
+    async fn run(mut self, mut engine: Engine, mut commands: mpsc::UnboundedReceiver<Command>) {
+        loop {
+            match self.serve(&mut engine, &mut commands).await {
+                SessionEnd::Closed => break,
+                SessionEnd::Lost(reason) => {
+                    self.connection_lost(&reason);
+                    if !self.reconnection.enabled {
+                        break;
+                    }
+                    match self.reconnect(&mut commands).await {
+                        Some(new_engine) => engine = new_engine,
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        unregister(&self.id, self.generation);
+        for (_, (_, reply)) in self.acks.drain() {
+            let _ = reply.send(Err("Connection closed".to_string()));
+        }
+        self.emit(SocketIoEventKind::Closed, None, |_| {});
+    }
+}
+
+fn error_message(data: Option<&Json>) -> String {
+    match data {
+        Some(Json::String(message)) => message.clone(),
+        Some(data) => data.get("message").and_then(Json::as_str).map_or_else(|| data.to_string(), str::to_string),
+        None => "Connection refused".to_string(),
+    }
+}
+
+fn send_command(connection_id: &str, command: Command) -> Result<Duration, String> {
+    let connections = get_connections().lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+    let handle = connections
+        .get(connection_id)
+        .ok_or_else(|| format!("Socket.IO connection {} is not open", connection_id))?;
+    handle
+        .commands
+        .send(command)
+        .map_err(|_| format!("Socket.IO connection {} is closed", connection_id))?;
+    Ok(handle.timeout)
+}
+
+/// 建立 Socket.IO 连接（仅 WebSocket 传输）并连接命名空间，之后的事件以 socketio-event 发出
+#[tauri::command]
+pub async fn socketio_connect(app: AppHandle, request: SocketIoConnectRequest) -> Result<SocketIoSession, String> {
+    let client_config = client_config_or_global(request.config)?;
+    let (engine_url, url_namespace) = engine_url(&request.url, request.path.as_deref(), &request.query)?;
+    let mut names: Vec<String> = request.namespaces.iter().map(|name| normalize_namespace(name)).collect();
+    if names.is_empty() {
+        names.push(url_namespace);
+    }
+    let mut seen = HashSet::new();
+    names.retain(|name| seen.insert(name.clone()));
+
+    // 连接前先占用 ID，期间收到的操作在连接建立后执行
+    let timeout = timeout_duration(client_config.timeout).unwrap_or(DEFAULT_TIMEOUT);
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let generation = register(&request.connection_id, sender, timeout)?;
+
+    let mut connection = Connection {
+        app,
+        id: request.connection_id,
+        generation,
+        engine_url,
+        headers: request.headers,
+        timeout,
+        client_config,
+        reconnection: request.reconnection,
+        namespaces: names
+            .into_iter()
+            .map(|name| Namespace {
+                name,
+                auth: request.auth.clone(),
+                sid: None,
+                connecting: false,
+                join_reply: None,
+            })
+            .collect(),
+        acks: HashMap::new(),
+        next_ack_id: 0,
+        binary: None,
+        queued: VecDeque::new(),
+    };
+
+    let engine = match connection.open_session().await {
+        Ok(engine) => engine,
+        Err(e) => {
+            unregister(&connection.id, generation);
+            return Err(e);
+        }
+    };
+    let session = SocketIoSession {
+        sid: engine.sid.clone(),
+        namespaces: connection
+            .namespaces
+            .iter()
+            .map(|state| SocketIoNamespace {
+                namespace: state.name.clone(),
+                sid: state.sid.clone().unwrap_or_default(),
+            })
+            .collect(),
+    };
+    tauri::async_runtime::spawn(connection.run(engine, receiver));
+    Ok(session)
+}
+
+/// 发送事件；`with_ack` 为 true 时等待服务端确认并返回确认参数。
+/// 重连期间发送的事件会在重连后发出
+#[tauri::command]
+pub async fn socketio_emit(
+    connection_id: String,
+    namespace: Option<String>,
+    event: String,
+    args: Vec<Json>,
+    with_ack: Option<bool>,
+) -> Result<Vec<Json>, String> {
+    let (reply, result) = oneshot::channel();
+    let command = Command::Emit {
+        namespace: normalize_namespace(namespace.as_deref().unwrap_or("/")),
+        event,
+        args,
+        with_ack: with_ack.unwrap_or(false),
+        reply,
+    };
+    let timeout = send_command(&connection_id, command)?;
+    tokio::time::timeout(timeout, result)
+        .await
+        .map_err(|_| "Timed out waiting for acknowledgement".to_string())?
+        .map_err(|_| "Socket.IO connection closed".to_string())?
+}
+
+/// 确认服务端发来的事件（事件的 ackId 不为空时）
+#[tauri::command]
+pub fn socketio_ack(connection_id: String, namespace: Option<String>, ack_id: u64, args: Vec<Json>) -> Result<(), String> {
+    let command = Command::Ack {
+        namespace: normalize_namespace(namespace.as_deref().unwrap_or("/")),
+        id: ack_id,
+        args,
+    };
+    send_command(&connection_id, command).map(|_| ())
+}
+
+/// 在已有连接上加入命名空间，返回命名空间的 sid
+#[tauri::command]
+pub async fn socketio_join(connection_id: String, namespace: String, auth: Option<Json>) -> Result<String, String> {
+    let (reply, result) = oneshot::channel();
+    let command = Command::Join {
+        namespace: normalize_namespace(&namespace),
+        auth,
+        reply,
+    };
+    let timeout = send_command(&connection_id, command)?;
+    tokio::time::timeout(timeout, result)
+        .await
+        .map_err(|_| "Timed out waiting for namespace connection".to_string())?
+        .map_err(|_| "Socket.IO connection closed".to_string())?
+}
+
+/// 离开命名空间
+#[tauri::command]
+pub fn socketio_leave(connection_id: String, namespace: String) -> Result<(), String> {
+    let command = Command::Leave {
+        namespace: normalize_namespace(&namespace),
+    };
+    send_command(&connection_id, command).map(|_| ())
+}
+
+/// 断开连接，不再重连
+#[tauri::command]
+pub fn socketio_disconnect(connection_id: String) -> Result<(), String> {
+    send_command(&connection_id, Command::Disconnect).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn round_trips_event_packets() {
+        let data = json!(["ev", {"a": 1}, "x"]);
+        let text = encode_packet(EVENT, "/ns", Some(12), Some(&data));
+        assert_eq!(text, r#"42/ns,12["ev",{"a":1},"x"]"#);
+
+        let packet = parse_packet(&text[1..]).unwrap();
+        assert_eq!(packet.kind, EVENT);
+        assert_eq!(packet.attachments, 0);
+        assert_eq!(packet.namespace, "/ns");
+        assert_eq!(packet.id, Some(12));
+        assert_eq!(packet.data, Some(data));
+
+        assert_eq!(encode_packet(EVENT, "/", None, Some(&json!(["ev"]))), r#"42["ev"]"#);
+        assert_eq!(encode_packet(ACK, "/", Some(3), Some(&json!([]))), "433[]");
+    }
+
+    #[test]
+    fn parses_default_namespace_packets() {
+        let packet = parse_packet("0").unwrap();
+        assert_eq!((packet.kind, packet.namespace.as_str(), packet.id, packet.data), (CONNECT, "/", None, None));
+
+        let packet = parse_packet(r#"0{"sid":"abc"}"#).unwrap();
+        assert_eq!(packet.namespace, "/");
+        assert_eq!(packet.data, Some(json!({"sid": "abc"})));
+
+        let packet = parse_packet("1/admin,").unwrap();
+        assert_eq!((packet.kind, packet.namespace.as_str(), packet.data), (DISCONNECT, "/admin", None));
+
+        let packet = parse_packet(r#"4/admin,{"message":"denied"}"#).unwrap();
+        assert_eq!(packet.kind, CONNECT_ERROR);
+        assert_eq!(packet.data, Some(json!({"message": "denied"})));
+
+        let packet = parse_packet("37[1]").unwrap();
+        assert_eq!((packet.kind, packet.id, packet.data), (ACK, Some(7), Some(json!([1]))));
+    }
+
+    #[test]
+    fn rejects_invalid_packets() {
+        assert!(parse_packet("").is_err());
+        assert!(parse_packet("7[]").is_err());
+        assert!(parse_packet("x").is_err());
+        assert!(parse_packet(r#"5["ev"]"#).is_err());
+        assert!(parse_packet("2[oops").is_err());
+    }
+
+    #[test]
+    fn fills_binary_placeholders() {
+        let packet = parse_packet(r#"52-/ns,5["ev",{"_placeholder":true,"num":0},{"nested":[{"_placeholder":true,"num":1}]}]"#).unwrap();
+        assert_eq!(packet.kind, BINARY_EVENT);
+        assert_eq!(packet.attachments, 2);
+        assert_eq!(packet.namespace, "/ns");
+        assert_eq!(packet.id, Some(5));
+
+        let mut data = packet.data.unwrap();
+        fill_placeholders(&mut data, &[vec![0, 1, 2], b"hi".to_vec()]);
+        assert_eq!(data, json!(["ev", "AAEC", {"nested": ["aGk="]}]));
+
+        let packet = parse_packet(r#"61-3[{"_placeholder":true,"num":0}]"#).unwrap();
+        assert_eq!((packet.kind, packet.attachments, packet.namespace.as_str(), packet.id), (BINARY_ACK, 1, "/", Some(3)));
+
+        // 编号超出附件数量时保持原样
+        let mut data = json!([{"_placeholder": true, "num": 4}]);
+        fill_placeholders(&mut data, &[]);
+        assert_eq!(data, json!([{"_placeholder": true, "num": 4}]));
+    }
+
+    #[test]
+    fn normalizes_namespaces() {
+        assert_eq!(normalize_namespace(""), "/");
+        assert_eq!(normalize_namespace("/"), "/");
+        assert_eq!(normalize_namespace(" admin/ "), "/admin");
+        assert_eq!(normalize_namespace("/chat/room/"), "/chat/room");
+    }
+
+    #[test]
+    fn builds_engine_urls() {
+        let query = HashMap::new();
+        assert_eq!(
+            engine_url("http://localhost:3000", None, &query).unwrap(),
+            ("http://localhost:3000/socket.io/?EIO=4&transport=websocket".to_string(), "/".to_string())
+        );
+        assert_eq!(
+            engine_url("https://example.com/admin/", Some(" custom "), &query).unwrap(),
+            ("https://example.com/custom/?EIO=4&transport=websocket".to_string(), "/admin".to_string())
+        );
+
+        let query = HashMap::from([("token".to_string(), "a b".to_string())]);
+        assert_eq!(
+            engine_url("ws://localhost/chat", Some("/io/"), &query).unwrap(),
+            ("ws://localhost/io/?token=a+b&EIO=4&transport=websocket".to_string(), "/chat".to_string())
+        );
+        assert!(engine_url("not a url", None, &query).is_err());
+    }
+}
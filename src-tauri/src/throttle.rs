@@ -0,0 +1,184 @@
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::body::ByteStream;
+
+/// 预设的网络环境
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NetworkPreset {
+    #[serde(rename = "slow3g")]
+    Slow3g,
+    #[serde(rename = "3g")]
+    Regular3g,
+    #[serde(rename = "4g")]
+    Regular4g,
+    FlakyWifi,
+    Offline,
+}
+
+/// 网络环境模拟：附加延迟、上下行限速和随机失败
+#[derive(Debug, Clone, Default, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NetworkConditions {
+    pub enabled: bool,
+    /// 预设参数，下面单独设置的字段会覆盖预设值
+    pub preset: Option<NetworkPreset>,
+    /// 每次请求附加的延迟（毫秒）
+    pub latency: Option<u64>,
+    /// 下行带宽上限（kbit/s），0 表示不限制
+    pub download_kbps: Option<u64>,
+    /// 上行带宽上限（kbit/s），0 表示不限制
+    pub upload_kbps: Option<u64>,
+    /// 随机失败的概率（百分比，0-100）
+    pub failure_rate: Option<u32>,
+}
+
+/// 生效的网络模拟参数
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NetworkProfile {
+    pub latency: Duration,
+    pub download_bytes_per_sec: Option<u64>,
+    pub upload_bytes_per_sec: Option<u64>,
+    pub failure_rate: u32,
+}
+
+impl NetworkPreset {
+    /// 预设的 (延迟毫秒, 下行 kbit/s, 上行 kbit/s, 失败百分比)
+    fn parameters(&self) -> (u64, u64, u64, u32) {
+        match self {
+            NetworkPreset::Slow3g => (400, 400, 400, 0),
+            NetworkPreset::Regular3g => (300, 1600, 750, 0),
+            NetworkPreset::Regular4g => (150, 9000, 9000, 0),
+            NetworkPreset::FlakyWifi => (80, 5000, 2000, 15),
+            NetworkPreset::Offline => (0, 0, 0, 100),
+        }
+    }
+}
+
+impl NetworkConditions {
+    pub fn profile(&self) -> NetworkProfile {
+        if !self.enabled {
+            return NetworkProfile::default();
+        }
+
+        let (latency, download_kbps, upload_kbps, failure_rate) =
+            self.preset.map(|preset| preset.parameters()).unwrap_or_default();
+        NetworkProfile {
+            latency: Duration::from_millis(self.latency.unwrap_or(latency)),
+            download_bytes_per_sec: kbps_to_bytes(self.download_kbps.unwrap_or(download_kbps)),
+            upload_bytes_per_sec: kbps_to_bytes(self.upload_kbps.unwrap_or(upload_kbps)),
+            failure_rate: self.failure_rate.unwrap_or(failure_rate).min(100),
+        }
+    }
+}
+
+fn kbps_to_bytes(kbps: u64) -> Option<u64> {
+    (kbps > 0).then(|| (kbps * 1000 / 8).max(1))
+}
+
+/// 按速率传输 `bytes` 字节所需的时间
+fn transfer_time(bytes: u64, bytes_per_sec: u64) -> Duration {
+    Duration::from_secs_f64(bytes as f64 / bytes_per_sec as f64)
+}
+
+/// 从 `start` 起按速率传输 `bytes` 字节还需要等待的时间
+fn pace_delay(start: Instant, bytes: u64, bytes_per_sec: u64) -> Option<Duration> {
+    transfer_time(bytes, bytes_per_sec).checked_sub(start.elapsed()).filter(|delay| !delay.is_zero())
+}
+
+impl NetworkProfile {
+    /// 本次尝试是否模拟失败
+    pub fn should_fail(&self) -> bool {
+        self.failure_rate > 0 && rand::random_range(0..100) < self.failure_rate
+    }
+}
+
+/// 按下行限速控制读取速度
+pub struct DownloadPacer {
+    rate: Option<u64>,
+    start: Instant,
+    received: u64,
+    /// 限速累计等待的时间
+    pub throttled: Duration,
+}
+
+impl DownloadPacer {
+    pub fn new(profile: &NetworkProfile) -> Self {
+        DownloadPacer {
+            rate: profile.download_bytes_per_sec,
+            start: Instant::now(),
+            received: 0,
+            throttled: Duration::ZERO,
+        }
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.rate.is_some()
+    }
+
+    /// 记录读到的字节数，读取速度超过上限时等待，直到平均速率回到上限以内
+    pub async fn consume(&mut self, bytes: usize) {
+        let Some(rate) = self.rate else {
+            return;
+        };
+
+        self.received += bytes as u64;
+        if let Some(delay) = pace_delay(self.start, self.received, rate) {
+            tokio::time::sleep(delay).await;
+            self.throttled += delay;
+        }
+    }
+}
+
+/// 按上行限速控制请求体的发送速度，每次尝试重新计速，等待时间累计到所有尝试
+pub struct UploadPacer {
+    rate: Option<u64>,
+    // 限速累计等待的时间（微秒），请求体流在 reqwest 中发送，需要共享
+    throttled: Arc<AtomicU64>,
+}
+
+impl UploadPacer {
+    pub fn new(profile: &NetworkProfile) -> Self {
+        UploadPacer {
+            rate: profile.upload_bytes_per_sec,
+            throttled: Arc::default(),
+        }
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.rate.is_some()
+    }
+
+    /// 限速累计等待的时间
+    pub fn throttled(&self) -> Duration {
+        Duration::from_micros(self.throttled.load(Ordering::Relaxed))
+    }
+
+    /// 发送每个数据块前等待，直到发送后的平均速率不超过上限
+    pub fn pace(&self, chunks: ByteStream) -> ByteStream {
+        let Some(rate) = self.rate else {
+            return chunks;
+        };
+        let throttled = self.throttled.clone();
+        let state = (chunks, Instant::now(), 0u64);
+        futures_util::stream::unfold(state, move |(mut chunks, start, mut sent)| {
+            let throttled = throttled.clone();
+            async move {
+                let chunk = chunks.next().await?;
+                if let Ok(chunk) = &chunk {
+                    sent += chunk.len() as u64;
+                    if let Some(delay) = pace_delay(start, sent, rate) {
+                        tokio::time::sleep(delay).await;
+                        throttled.fetch_add(delay.as_micros() as u64, Ordering::Relaxed);
+                    }
+                }
+                Some((chunk, (chunks, start, sent)))
+            }
+        })
+        .boxed()
+    }
+}
@@ -0,0 +1,817 @@
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::{ClientSessionMemoryCache, ClientSessionStore, Resumption, WebPkiServerVerifier};
+use hyper_util::client::legacy::connect::{Connection, HttpInfo};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{CertificateError, DigitallySignedStruct, NamedGroup, ProtocolVersion, RootCertStore, SignatureScheme};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use crate::dns::{match_host, OverrideResolver};
+use crate::http_client::{get_global_config, timeout_duration, ClientConfig};
+
+/// TLS 版本、证书校验和 SNI 相关选项
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TlsOptions {
+    /// 最低 TLS 版本，如 "1.2"
+    pub min_version: Option<String>,
+    /// 最高 TLS 版本，如 "1.3"
+    pub max_version: Option<String>,
+    /// 为 false 时仍校验证书链，但不校验主机名
+    pub verify_hostname: bool,
+    /// 覆盖发送的 SNI，空字符串表示不发送 SNI
+    pub server_name: Option<String>,
+    /// 为 false 时只信任 ca_cert_paths 中的证书
+    pub use_system_roots: bool,
+    pub pinned_keys: Vec<PinnedKey>,
+}
+
+impl Default for TlsOptions {
+    fn default() -> Self {
+        TlsOptions {
+            min_version: None,
+            max_version: None,
+            verify_hostname: true,
+            server_name: None,
+            use_system_roots: true,
+            pinned_keys: Vec::new(),
+        }
+    }
+}
+
+/// 按主机固定证书公钥
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinnedKey {
+    /// 主机名，支持 `*.example.com` 通配符
+    pub host: String,
+    /// SubjectPublicKeyInfo 的 SHA-256（base64），可带 `sha256/` 前缀
+    pub spki_sha256: Vec<String>,
+}
+
+/// 一次 TLS 握手协商出的参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsDetails {
+    /// 完整握手时取自证书签名所用的版本，会话恢复时取自恢复的会话
+    pub protocol_version: Option<String>,
+    /// reqwest 不公开底层的 rustls 连接，HTTP 请求无法得知协商的套件，此时为空（检查证书时可用）
+    pub cipher_suite: Option<String>,
+    /// TLS 1.2 下 rustls 不公开协商的密钥交换组，此时为空
+    pub key_exchange_group: Option<String>,
+    /// 会话恢复时没有证书签名，此时为空
+    pub signature_scheme: Option<String>,
+    /// 协商出 h2 时为 h2，否则为按 HTTP/1.1 通信时提供的 http/1.1（服务端未回应 ALPN 时也是如此）
+    pub alpn: Option<String>,
+    pub sni: Option<String>,
+}
+
+/// 证书的主要字段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub serial_number: String,
+    pub subject_alt_names: Vec<String>,
+    pub not_before: String,
+    pub not_after: String,
+    pub expired: bool,
+    pub is_ca: bool,
+    pub public_key_algorithm: String,
+    pub sha256_fingerprint: String,
+    pub sha1_fingerprint: String,
+    /// SubjectPublicKeyInfo 的 SHA-256（base64）
+    pub spki_sha256: String,
+}
+
+/// 证书链检查结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateInspection {
+    pub host: String,
+    pub port: u16,
+    pub sni: String,
+    pub remote_addr: String,
+    pub protocol_version: Option<String>,
+    pub cipher_suite: Option<String>,
+    pub alpn: Option<String>,
+    pub chain: Vec<CertificateInfo>,
+    pub trusted: bool,
+    pub validation_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct HandshakeRecord {
+    details: TlsDetails,
+    chain: Vec<CertificateDer<'static>>,
+    // 完整握手时证书签名所用的版本
+    signed_version: Option<ProtocolVersion>,
+    // 握手开始时提供给服务端恢复的会话版本
+    resumable_version: Option<ProtocolVersion>,
+}
+
+impl HandshakeRecord {
+    // rustls 只在完整握手时校验证书签名，没有签名说明恢复了提供的会话
+    fn finish(&mut self) -> bool {
+        let Some(version) = self.signed_version.or(self.resumable_version) else {
+            return false;
+        };
+        self.details.protocol_version = Some(protocol_name(version));
+        true
+    }
+}
+
+tokio::task_local! {
+    // 正在建立的连接的握手记录，由 HandshakeRecorder 设置，rustls 的回调写入
+    static CURRENT_HANDSHAKE: Arc<Mutex<HandshakeRecord>>;
+}
+
+// 每个 client 最多保留的连接记录数，超出时丢弃最早的连接
+const MAX_RECORDED_CONNECTIONS: usize = 512;
+
+fn record_handshake(update: impl FnOnce(&mut HandshakeRecord)) {
+    let _ = CURRENT_HANDSHAKE.try_with(|record| {
+        if let Ok(mut record) = record.lock() {
+            update(&mut record);
+        }
+    });
+}
+
+type ConnectionKey = (SocketAddr, SocketAddr);
+
+// client 建立的一条连接
+#[derive(Default)]
+struct ConnectionRecord {
+    // 非 TLS 连接为空
+    handshake: Option<HandshakeRecord>,
+    // 已有响应使用过此连接
+    used: bool,
+}
+
+#[derive(Default)]
+struct RecordedConnections {
+    records: HashMap<ConnectionKey, ConnectionRecord>,
+    order: VecDeque<ConnectionKey>,
+}
+
+/// 按连接（本地地址, 远端地址）记录 client 建立的每条连接及其 TLS 握手信息，作为 reqwest 的连接器中间层使用
+#[derive(Clone)]
+pub struct HandshakeRecorder {
+    connections: Arc<Mutex<RecordedConnections>>,
+    // TLS 握手时通过 ALPN 提供的协议
+    alpn_protocols: Arc<Vec<Vec<u8>>>,
+}
+
+impl HandshakeRecorder {
+    pub fn new(alpn_protocols: Vec<Vec<u8>>) -> Self {
+        HandshakeRecorder {
+            connections: Arc::default(),
+            alpn_protocols: Arc::new(alpn_protocols),
+        }
+    }
+
+    // 新连接替换地址相同的旧记录，本地端口被系统重用时不会误判为复用
+    fn insert(&self, key: ConnectionKey, record: ConnectionRecord) {
+        let Ok(mut connections) = self.connections.lock() else {
+            return;
+        };
+        if connections.records.insert(key, record).is_none() {
+            connections.order.push_back(key);
+        }
+        while connections.order.len() > MAX_RECORDED_CONNECTIONS {
+            if let Some(oldest) = connections.order.pop_front() {
+                connections.records.remove(&oldest);
+            }
+        }
+    }
+
+    fn with_record<T>(&self, info: &HttpInfo, read: impl FnOnce(&HandshakeRecord) -> T) -> Option<T> {
+        let connections = self.connections.lock().ok()?;
+        connections
+            .records
+            .get(&(info.local_addr(), info.remote_addr()))
+            .and_then(|record| record.handshake.as_ref())
+            .map(read)
+    }
+
+    /// 标记响应所在的连接已被使用，返回此前是否已有响应使用过它；连接记录已被丢弃时返回 None
+    pub fn mark_used(&self, info: &HttpInfo) -> Option<bool> {
+        let mut connections = self.connections.lock().ok()?;
+        let record = connections.records.get_mut(&(info.local_addr(), info.remote_addr()))?;
+        Some(std::mem::replace(&mut record.used, true))
+    }
+
+    /// 获取响应所在连接的握手信息
+    pub fn details(&self, info: &HttpInfo) -> Option<TlsDetails> {
+        self.with_record(info, |record| record.details.clone())
+    }
+
+    /// 获取响应所在连接握手时服务器发送的证书链，会话恢复时服务器不发送证书
+    pub fn certificates(&self, info: &HttpInfo) -> Option<Vec<CertificateInfo>> {
+        let chain = self.with_record(info, |record| record.chain.clone())?;
+        if chain.is_empty() {
+            return None;
+        }
+        chain.iter().map(|cert| describe_certificate(cert)).collect::<Result<Vec<_>, _>>().ok()
+    }
+}
+
+impl<S> tower::Layer<S> for HandshakeRecorder {
+    type Service = RecordHandshake<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RecordHandshake {
+            inner,
+            recorder: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RecordHandshake<S> {
+    inner: S,
+    recorder: HandshakeRecorder,
+}
+
+impl<S, R> tower::Service<R> for RecordHandshake<S>
+where
+    S: tower::Service<R>,
+    S::Response: Connection,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let record = Arc::new(Mutex::new(HandshakeRecord::default()));
+        let connecting = CURRENT_HANDSHAKE.scope(record.clone(), self.inner.call(request));
+        let recorder = self.recorder.clone();
+        Box::pin(async move {
+            let connection = connecting.await?;
+            let connected = connection.connected();
+            let mut extras = http::Extensions::new();
+            connected.get_extras(&mut extras);
+            if let (Some(info), Ok(mut record)) = (extras.get::<HttpInfo>(), record.lock()) {
+                // 非 TLS 连接不会触发任何回调
+                let handshake = record.finish().then(|| {
+                    // reqwest 只告诉我们是否协商出了 h2，其他情况下按 HTTP/1.1 通信
+                    record.details.alpn = if connected.is_negotiated_h2() {
+                        Some("h2".to_string())
+                    } else {
+                        recorder
+                            .alpn_protocols
+                            .iter()
+                            .any(|protocol| protocol == b"http/1.1")
+                            .then(|| "http/1.1".to_string())
+                    };
+                    std::mem::take(&mut *record)
+                });
+                let key = (info.local_addr(), info.remote_addr());
+                recorder.insert(key, ConnectionRecord { handshake, used: false });
+            }
+            Ok(connection)
+        })
+    }
+}
+
+fn protocol_name(version: ProtocolVersion) -> String {
+    match version {
+        ProtocolVersion::TLSv1_2 => "TLSv1.2".to_string(),
+        ProtocolVersion::TLSv1_3 => "TLSv1.3".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+fn hex_fingerprint(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(":")
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+/// 解析 DER 证书
+pub fn describe_certificate(der: &[u8]) -> Result<CertificateInfo, String> {
+    use base64::Engine;
+    use x509_parser::extensions::GeneralName;
+
+    let (_, cert) = x509_parser::parse_x509_certificate(der)
+        .map_err(|e| format!("Failed to parse certificate: {}", e))?;
+
+    let mut subject_alt_names = Vec::new();
+    if let Ok(Some(san)) = cert.subject_alternative_name() {
+        for name in &san.value.general_names {
+            match name {
+                GeneralName::DNSName(dns) => subject_alt_names.push(format!("DNS:{}", dns)),
+                GeneralName::IPAddress(bytes) => {
+                    let ip = match bytes.len() {
+                        4 => <[u8; 4]>::try_from(*bytes).ok().map(|b| std::net::IpAddr::from(b).to_string()),
+                        16 => <[u8; 16]>::try_from(*bytes).ok().map(|b| std::net::IpAddr::from(b).to_string()),
+                        _ => None,
+                    };
+                    subject_alt_names.push(format!("IP:{}", ip.unwrap_or_else(|| hex_fingerprint(bytes))));
+                }
+                GeneralName::RFC822Name(email) => subject_alt_names.push(format!("email:{}", email)),
+                GeneralName::URI(uri) => subject_alt_names.push(format!("URI:{}", uri)),
+                _ => {}
+            }
+        }
+    }
+
+    let public_key_algorithm = match cert.public_key().algorithm.algorithm.to_id_string().as_str() {
+        "1.2.840.113549.1.1.1" => "RSA".to_string(),
+        "1.2.840.10045.2.1" => "EC".to_string(),
+        "1.3.101.112" => "Ed25519".to_string(),
+        "1.3.101.113" => "Ed448".to_string(),
+        other => other.to_string(),
+    };
+
+    let validity = cert.validity();
+    Ok(CertificateInfo {
+        subject: cert.subject().to_string(),
+        issuer: cert.issuer().to_string(),
+        serial_number: cert.raw_serial_as_string(),
+        subject_alt_names,
+        not_before: format_timestamp(validity.not_before.timestamp()),
+        not_after: format_timestamp(validity.not_after.timestamp()),
+        expired: !validity.is_valid(),
+        is_ca: cert.is_ca(),
+        public_key_algorithm,
+        sha256_fingerprint: hex_fingerprint(&Sha256::digest(der)),
+        sha1_fingerprint: hex_fingerprint(&Sha1::digest(der)),
+        spki_sha256: base64::engine::general_purpose::STANDARD.encode(Sha256::digest(cert.public_key().raw)),
+    })
+}
+
+fn spki_sha256(der: &[u8]) -> Option<String> {
+    use base64::Engine;
+
+    let (_, cert) = x509_parser::parse_x509_certificate(der).ok()?;
+    Some(base64::engine::general_purpose::STANDARD.encode(Sha256::digest(cert.public_key().raw)))
+}
+
+/// 证书链、主机名和公钥固定的校验逻辑
+#[derive(Debug)]
+struct CertificateChecker {
+    // 为 None 时不校验证书链（verify_ssl = false），公钥固定仍然生效，
+    // 握手签名始终校验，否则对方不持有私钥也能通过公钥固定
+    webpki: Option<Arc<WebPkiServerVerifier>>,
+    verify_hostname: bool,
+    pinned_keys: Vec<PinnedKey>,
+}
+
+impl CertificateChecker {
+    fn new(client_config: &ClientConfig, provider: &Arc<CryptoProvider>, verify_chain: bool) -> Result<Self, String> {
+        let webpki = if verify_chain {
+            let roots = load_root_store(client_config)?;
+            let verifier = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                .build()
+                .map_err(|e| format!("Failed to build certificate verifier: {}", e))?;
+            Some(verifier)
+        } else {
+            None
+        };
+
+        Ok(CertificateChecker {
+            webpki,
+            verify_hostname: client_config.tls.verify_hostname,
+            pinned_keys: client_config.tls.pinned_keys.clone(),
+        })
+    }
+
+    fn check(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<(), rustls::Error> {
+        if let Some(webpki) = &self.webpki {
+            match webpki.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now) {
+                Ok(_) => {}
+                Err(rustls::Error::InvalidCertificate(
+                    CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. },
+                )) if !self.verify_hostname => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.check_pins(end_entity, intermediates, server_name)
+    }
+
+    /// 证书链中任意一张证书的公钥命中即通过
+    fn check_pins(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+    ) -> Result<(), rustls::Error> {
+        let host = server_name.to_str();
+        let pins: Vec<&str> = self
+            .pinned_keys
+            .iter()
+            .filter(|pinned| match_host(&pinned.host, &host).is_some())
+            .flat_map(|pinned| pinned.spki_sha256.iter())
+            .map(|pin| pin.trim().trim_start_matches("sha256/"))
+            .collect();
+        if pins.is_empty() {
+            return Ok(());
+        }
+
+        let matched = std::iter::once(end_entity)
+            .chain(intermediates)
+            .filter_map(|cert| spki_sha256(cert))
+            .any(|hash| pins.contains(&hash.as_str()));
+        if matched {
+            Ok(())
+        } else {
+            Err(rustls::Error::General(format!(
+                "certificate public key pin mismatch for {} (leaf sha256/{})",
+                host,
+                spki_sha256(end_entity).unwrap_or_default()
+            )))
+        }
+    }
+}
+
+/// 包装证书校验器，顺便记录握手使用的证书链和签名算法
+#[derive(Debug)]
+struct RecordingVerifier {
+    checker: CertificateChecker,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for RecordingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        record_handshake(|record| {
+            record.chain = std::iter::once(end_entity)
+                .chain(intermediates)
+                .map(|cert| cert.clone().into_owned())
+                .collect();
+        });
+
+        self.checker.check(end_entity, intermediates, server_name, ocsp_response, now)?;
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        record_handshake(|record| {
+            record.signed_version = Some(ProtocolVersion::TLSv1_2);
+            record.details.signature_scheme = Some(format!("{:?}", dss.scheme));
+        });
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        record_handshake(|record| {
+            record.signed_version = Some(ProtocolVersion::TLSv1_3);
+            record.details.signature_scheme = Some(format!("{:?}", dss.scheme));
+        });
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// 包装会话缓存，记录 SNI、TLS 1.3 协商的密钥交换组和提供给服务端恢复的会话
+#[derive(Debug)]
+struct RecordingSessionStore {
+    inner: ClientSessionMemoryCache,
+}
+
+impl ClientSessionStore for RecordingSessionStore {
+    fn set_kx_hint(&self, server_name: ServerName<'static>, group: NamedGroup) {
+        record_handshake(|record| record.details.key_exchange_group = Some(format!("{:?}", group)));
+        self.inner.set_kx_hint(server_name, group);
+    }
+
+    fn kx_hint(&self, server_name: &ServerName<'_>) -> Option<NamedGroup> {
+        self.inner.kx_hint(server_name)
+    }
+
+    fn set_tls12_session(&self, server_name: ServerName<'static>, value: rustls::client::Tls12ClientSessionValue) {
+        self.inner.set_tls12_session(server_name, value);
+    }
+
+    fn tls12_session(&self, server_name: &ServerName<'_>) -> Option<rustls::client::Tls12ClientSessionValue> {
+        // 只在没有 TLS 1.3 票据时查找
+        let session = self.inner.tls12_session(server_name);
+        if session.is_some() {
+            record_handshake(|record| record.resumable_version = Some(ProtocolVersion::TLSv1_2));
+        }
+        session
+    }
+
+    fn remove_tls12_session(&self, server_name: &ServerName<'static>) {
+        self.inner.remove_tls12_session(server_name);
+    }
+
+    fn insert_tls13_ticket(&self, server_name: ServerName<'static>, value: rustls::client::Tls13ClientSessionValue) {
+        self.inner.insert_tls13_ticket(server_name, value);
+    }
+
+    fn take_tls13_ticket(&self, server_name: &ServerName<'static>) -> Option<rustls::client::Tls13ClientSessionValue> {
+        // 每次握手开始时都会先查找可恢复的会话
+        let ticket = self.inner.take_tls13_ticket(server_name);
+        record_handshake(|record| {
+            record.details.sni = Some(server_name.to_str().to_string());
+            record.resumable_version = ticket.is_some().then_some(ProtocolVersion::TLSv1_3);
+        });
+        ticket
+    }
+}
+
+/// 读取系统证书库和自定义 CA
+fn load_root_store(client_config: &ClientConfig) -> Result<RootCertStore, String> {
+    let mut roots = RootCertStore::empty();
+
+    if client_config.tls.use_system_roots {
+        let native = rustls_native_certs::load_native_certs();
+        for error in &native.errors {
+            log::warn!("Failed to load native certificate: {}", error);
+        }
+        roots.add_parsable_certificates(native.certs);
+        if roots.is_empty() {
+            // 系统证书库不可用时回退到内置的 Mozilla 根证书
+            roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        }
+    }
+
+    for ca_cert_path in &client_config.ca_cert_paths {
+        let ca_cert_pem = std::fs::read(ca_cert_path)
+            .map_err(|e| format!("Failed to read CA certificate from {}: {}", ca_cert_path, e))?;
+        for cert in CertificateDer::pem_slice_iter(&ca_cert_pem) {
+            let cert = cert.map_err(|e| format!("Failed to parse CA certificate from {}: {}", ca_cert_path, e))?;
+            roots
+                .add(cert)
+                .map_err(|e| format!("Failed to parse CA certificate from {}: {}", ca_cert_path, e))?;
+        }
+    }
+
+    if roots.is_empty() {
+        return Err("No trusted CA certificates: system roots are disabled and no CA certificate paths are set".to_string());
+    }
+
+    Ok(roots)
+}
+
+fn parse_tls_version(version: Option<&str>, default: u8) -> Result<u8, String> {
+    let Some(version) = version.map(str::trim).filter(|v| !v.is_empty()) else {
+        return Ok(default);
+    };
+    let normalized = version.trim_start_matches("TLSv").trim_start_matches("TLS").trim();
+    match normalized {
+        "1.0" => Ok(10),
+        "1.1" => Ok(11),
+        "1.2" => Ok(12),
+        "1.3" => Ok(13),
+        _ => Err(format!("Unsupported TLS version: {}", version)),
+    }
+}
+
+/// 按最低/最高版本筛选可用的协议版本（rustls 只支持 TLS 1.2 和 1.3）
+fn protocol_versions(options: &TlsOptions) -> Result<Vec<&'static rustls::SupportedProtocolVersion>, String> {
+    let min = parse_tls_version(options.min_version.as_deref(), 12)?;
+    let max = parse_tls_version(options.max_version.as_deref(), 13)?;
+
+    let versions: Vec<&'static rustls::SupportedProtocolVersion> = [(12, &rustls::version::TLS12), (13, &rustls::version::TLS13)]
+        .into_iter()
+        .filter(|(version, _)| *version >= min && *version <= max)
+        .map(|(_, supported)| supported)
+        .collect();
+    if versions.is_empty() {
+        return Err("No supported TLS version in the configured range (only TLS 1.2 and 1.3 are available)".to_string());
+    }
+    Ok(versions)
+}
+
+/// 覆盖的 SNI 名称（不含空字符串，空字符串表示禁用 SNI）
+pub fn sni_override(options: &TlsOptions) -> Option<&str> {
+    options.server_name.as_deref().map(str::trim).filter(|name| !name.is_empty())
+}
+
+fn sni_disabled(options: &TlsOptions) -> bool {
+    options.server_name.as_deref().is_some_and(|name| name.trim().is_empty())
+}
+
+/// 根据客户端配置构建 rustls 配置，交给 reqwest 使用
+pub fn build_tls_config(client_config: &ClientConfig) -> Result<rustls::ClientConfig, String> {
+    let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+
+    let verifier = RecordingVerifier {
+        checker: CertificateChecker::new(client_config, &provider, client_config.verify_ssl)?,
+        provider: provider.clone(),
+    };
+
+    let mut tls_config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_protocol_versions(&protocol_versions(&client_config.tls)?)
+        .map_err(|e| format!("Failed to configure TLS: {}", e))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+
+    tls_config.enable_sni = !sni_disabled(&client_config.tls);
+    tls_config.alpn_protocols = client_config.http_version.alpn_protocols();
+    tls_config.resumption = Resumption::store(Arc::new(RecordingSessionStore {
+        inner: ClientSessionMemoryCache::new(256),
+    }));
+
+    Ok(tls_config)
+}
+
+/// 检查用校验器：总是完成握手，只记录证书链校验的结果
+#[derive(Debug)]
+struct InspectingVerifier {
+    checker: CertificateChecker,
+    provider: Arc<CryptoProvider>,
+    validation_error: Mutex<Option<String>>,
+}
+
+impl ServerCertVerifier for InspectingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if let Err(e) = self.checker.check(end_entity, intermediates, server_name, ocsp_response, now)
+            && let Ok(mut validation_error) = self.validation_error.lock()
+        {
+            *validation_error = Some(e.to_string());
+        }
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// 连接服务器并获取证书链，按当前信任配置校验（不受 verify_ssl 影响）
+#[tauri::command]
+pub async fn inspect_certificate(url: String, config: Option<ClientConfig>) -> Result<CertificateInspection, String> {
+    use reqwest::dns::{Name, Resolve};
+    use tokio::net::TcpStream;
+
+    let client_config = match config {
+        Some(config) => config,
+        None => {
+            let global_config = get_global_config();
+            let config_guard = global_config.lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+            config_guard.clone()
+        }
+    };
+    let timeout = timeout_duration(client_config.timeout).unwrap_or(Duration::MAX);
+    let connect_timeout = client_config.connect_timeout.and_then(timeout_duration).unwrap_or(timeout);
+
+    let parsed_url = reqwest::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
+    let host = parsed_url
+        .host_str()
+        .ok_or("URL has no host")?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = parsed_url.port_or_known_default().unwrap_or(443);
+
+    // 解析地址（应用 DNS 覆盖）
+    let resolver = OverrideResolver::new(&client_config.resolve_overrides)?;
+    let name = Name::from_str(&host).map_err(|_| format!("Invalid host: {}", host))?;
+    let addrs: Vec<std::net::SocketAddr> = resolver
+        .resolve(name)
+        .await
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+        .map(|mut addr| {
+            addr.set_port(port);
+            addr
+        })
+        .collect();
+
+    let mut last_error = format!("No addresses found for {}", host);
+    let mut tcp_stream = None;
+    for addr in addrs {
+        match tokio::time::timeout(connect_timeout, TcpStream::connect(addr)).await {
+            Ok(Ok(stream)) => {
+                tcp_stream = Some((stream, addr));
+                break;
+            }
+            Ok(Err(e)) => last_error = format!("Failed to connect to {}: {}", addr, e),
+            Err(_) => last_error = format!("Connection to {} timed out", addr),
+        }
+    }
+    let (tcp_stream, remote_addr) = tcp_stream.ok_or(last_error)?;
+
+    let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+    let verifier = Arc::new(InspectingVerifier {
+        checker: CertificateChecker::new(&client_config, &provider, true)?,
+        provider: provider.clone(),
+        validation_error: Mutex::new(None),
+    });
+
+    let mut tls_config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_protocol_versions(&protocol_versions(&client_config.tls)?)
+        .map_err(|e| format!("Failed to configure TLS: {}", e))?
+        .dangerous()
+        .with_custom_certificate_verifier(verifier.clone())
+        .with_no_client_auth();
+    tls_config.enable_sni = !sni_disabled(&client_config.tls);
+    tls_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    let sni_host = sni_override(&client_config.tls).unwrap_or(&host).to_string();
+    let server_name = ServerName::try_from(sni_host.clone()).map_err(|e| format!("Invalid server name {}: {}", sni_host, e))?;
+    let sni = if sni_disabled(&client_config.tls) {
+        String::new()
+    } else {
+        server_name.to_str().to_string()
+    };
+    let connector = tokio_rustls::TlsConnector::from(Arc::new(tls_config));
+    let tls_stream = tokio::time::timeout(connect_timeout, connector.connect(server_name, tcp_stream))
+        .await
+        .map_err(|_| format!("TLS handshake with {} timed out", remote_addr))?
+        .map_err(|e| format!("TLS handshake failed: {}", e))?;
+
+    let (_, connection) = tls_stream.get_ref();
+    let chain = connection
+        .peer_certificates()
+        .unwrap_or_default()
+        .iter()
+        .map(|cert| describe_certificate(cert))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let validation_error = verifier
+        .validation_error
+        .lock()
+        .map_err(|e| format!("Failed to acquire lock: {}", e))?
+        .clone();
+
+    Ok(CertificateInspection {
+        host,
+        port,
+        sni,
+        remote_addr: remote_addr.to_string(),
+        protocol_version: connection.protocol_version().map(protocol_name),
+        cipher_suite: connection.negotiated_cipher_suite().map(|suite| format!("{:?}", suite.suite())),
+        alpn: connection.alpn_protocol().map(|alpn| String::from_utf8_lossy(alpn).to_string()),
+        chain,
+        trusted: validation_error.is_none(),
+        validation_error,
+    })
+}
@@ -0,0 +1,376 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use reqwest::dns::{Name, Resolve};
+use rustls::pki_types::ServerName;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::error::{Error as WsError, ProtocolError, SubProtocolError};
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::WebSocketStream;
+
+use crate::dns::OverrideResolver;
+use crate::http_client::{self, timeout_duration, ClientConfig, ProxyConfig};
+use crate::tls;
+
+// 未配置连接超时时，连接单个地址的最长时间，超时后尝试下一个地址
+const ADDRESS_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// WebSocket 连接的底层传输（TCP 或 TLS）
+pub trait WsIo: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> WsIo for T {}
+
+pub type WsStream = WebSocketStream<Box<dyn WsIo>>;
+
+/// 已建立的 WebSocket 连接
+pub struct WsConnection {
+    pub stream: WsStream,
+    /// 服务端选定的子协议（Sec-WebSocket-Protocol）
+    pub protocol: Option<String>,
+}
+
+/// 按客户端配置建立 WebSocket 连接：应用代理、DNS 覆盖和 TLS/CA 设置，并与 HTTP 请求共享 cookie；
+/// `protocols` 按优先顺序列出请求的子协议
+pub async fn connect(
+    url: &str,
+    headers: &HashMap<String, String>,
+    protocols: &[&str],
+    client_config: &ClientConfig,
+) -> Result<WsConnection, String> {
+    let mut parsed_url = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+    let secure = match parsed_url.scheme() {
+        "ws" | "http" => false,
+        "wss" | "https" => true,
+        scheme => return Err(format!("Unsupported WebSocket scheme: {}", scheme)),
+    };
+    // 握手请求只接受 ws/wss
+    let _ = parsed_url.set_scheme(if secure { "wss" } else { "ws" });
+    let host = parsed_url
+        .host_str()
+        .ok_or("URL has no host")?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = parsed_url.port_or_known_default().unwrap_or(if secure { 443 } else { 80 });
+    // cookie 按对应的 http(s) 地址匹配
+    let mut cookie_url = parsed_url.clone();
+    let _ = cookie_url.set_scheme(if secure { "https" } else { "http" });
+
+    let build_request = |protocols: &[&str]| -> Result<Request, String> {
+        let mut request = parsed_url
+            .as_str()
+            .into_client_request()
+            .map_err(|e| format!("Invalid WebSocket request: {}", e))?;
+        let request_headers = request.headers_mut();
+        if !client_config.user_agent.is_empty() {
+            let user_agent =
+                HeaderValue::from_str(&client_config.user_agent).map_err(|e| format!("Invalid user agent: {}", e))?;
+            request_headers.insert("user-agent", user_agent);
+        }
+        for (key, value) in headers {
+            let name = HeaderName::from_bytes(key.as_bytes()).map_err(|e| format!("Invalid header name {}: {}", key, e))?;
+            let value = HeaderValue::from_str(value).map_err(|e| format!("Invalid header value for {}: {}", key, e))?;
+            request_headers.insert(name, value);
+        }
+        if !request_headers.contains_key("cookie")
+            && let Some(cookie) = http_client::cookie_header(&cookie_url)
+        {
+            request_headers.insert("cookie", cookie);
+        }
+        if !protocols.is_empty() && !request_headers.contains_key("sec-websocket-protocol") {
+            let value = HeaderValue::from_str(&protocols.join(", ")).map_err(|e| format!("Invalid subprotocol: {}", e))?;
+            request_headers.insert("sec-websocket-protocol", value);
+        }
+        Ok(request)
+    };
+    let request = build_request(protocols)?;
+
+    let timeout = timeout_duration(client_config.timeout).unwrap_or(Duration::MAX);
+    let connect_timeout = client_config.connect_timeout.and_then(timeout_duration).unwrap_or(timeout);
+
+    let handshake = async {
+        let stream = open_stream(client_config, &host, port, secure).await?;
+        match tokio_tungstenite::client_async_with_config(request, stream, None).await {
+            // 与浏览器一致：服务端没有选择子协议时也允许连接，不带子协议重新握手
+            Err(WsError::Protocol(ProtocolError::SecWebSocketSubProtocolError(SubProtocolError::NoSubProtocol))) => {
+                let stream = open_stream(client_config, &host, port, secure).await?;
+                tokio_tungstenite::client_async_with_config(build_request(&[])?, stream, None)
+                    .await
+                    .map_err(|e| format!("WebSocket handshake failed: {}", e))
+            }
+            result => result.map_err(|e| format!("WebSocket handshake failed: {}", e)),
+        }
+    };
+    let (stream, response) = tokio::time::timeout(connect_timeout, handshake)
+        .await
+        .map_err(|_| format!("Connection to {} timed out", url))??;
+
+    http_client::store_response_cookies(&cookie_url, response.headers());
+    let protocol = response
+        .headers()
+        .get("sec-websocket-protocol")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_string());
+    Ok(WsConnection { stream, protocol })
+}
+
+/// 建立 TCP 连接（直连或经代理），wss 再完成 TLS 握手
+async fn open_stream(client_config: &ClientConfig, host: &str, port: u16, secure: bool) -> Result<Box<dyn WsIo>, String> {
+    let stream: Box<dyn WsIo> = if client_config.proxy.enabled {
+        connect_via_proxy(client_config, host, port).await?
+    } else {
+        Box::new(connect_direct(client_config, host, port).await?)
+    };
+    if !secure {
+        return Ok(stream);
+    }
+
+    let sni_host = tls::sni_override(&client_config.tls).unwrap_or(host);
+    Ok(Box::new(tls_handshake(client_config, sni_host, stream).await?))
+}
+
+/// 按客户端的 TLS/CA 设置完成 TLS 握手
+async fn tls_handshake<S: WsIo>(
+    client_config: &ClientConfig,
+    server_name: &str,
+    stream: S,
+) -> Result<tokio_rustls::client::TlsStream<S>, String> {
+    let mut tls_config = tls::build_tls_config(client_config)?;
+    // WebSocket 握手和 CONNECT 都需要 HTTP/1.1
+    tls_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    let name = ServerName::try_from(server_name.to_string())
+        .map_err(|e| format!("Invalid server name {}: {}", server_name, e))?;
+    let connector = tokio_rustls::TlsConnector::from(Arc::new(tls_config));
+    connector
+        .connect(name, stream)
+        .await
+        .map_err(|e| format!("TLS handshake failed: {}", e))
+}
+
+async fn connect_direct(client_config: &ClientConfig, host: &str, port: u16) -> Result<TcpStream, String> {
+    // 解析地址（应用 DNS 覆盖）
+    let resolver = OverrideResolver::new(&client_config.resolve_overrides)?;
+    let name = Name::from_str(host).map_err(|_| format!("Invalid host: {}", host))?;
+    let addrs = resolver
+        .resolve(name)
+        .await
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?;
+
+    // 与 hyper 相同：连接超时平均分给各个地址，一个地址无响应时仍有时间尝试其他地址
+    let addrs: Vec<_> = addrs.collect();
+    let address_timeout = client_config
+        .connect_timeout
+        .and_then(timeout_duration)
+        .map_or(ADDRESS_CONNECT_TIMEOUT, |timeout| timeout / addrs.len().max(1) as u32);
+
+    let mut last_error = format!("No addresses found for {}", host);
+    for mut addr in addrs {
+        addr.set_port(port);
+        match tokio::time::timeout(address_timeout, TcpStream::connect(addr)).await {
+            Ok(Ok(stream)) => {
+                let _ = stream.set_nodelay(true);
+                return Ok(stream);
+            }
+            Ok(Err(e)) => last_error = format!("Failed to connect to {}: {}", addr, e),
+            Err(_) => last_error = format!("Connection to {} timed out", addr),
+        }
+    }
+    Err(last_error)
+}
+
+/// 通过代理建立到目标的隧道，协议与 HTTP 请求相同：SOCKS5、经 TLS 的 HTTP CONNECT（https），其余按 HTTP CONNECT
+async fn connect_via_proxy(client_config: &ClientConfig, host: &str, port: u16) -> Result<Box<dyn WsIo>, String> {
+    let proxy = &client_config.proxy;
+    let mut stream = TcpStream::connect((proxy.host.as_str(), proxy.port))
+        .await
+        .map_err(|e| format!("Failed to connect to proxy {}:{}: {}", proxy.host, proxy.port, e))?;
+    let _ = stream.set_nodelay(true);
+    match proxy.protocol.as_str() {
+        "socks5" => {
+            socks5_handshake(&mut stream, proxy, host, port).await?;
+            Ok(Box::new(stream))
+        }
+        "https" => {
+            let mut stream = tls_handshake(client_config, &proxy.host, stream).await?;
+            http_connect(&mut stream, proxy, host, port).await?;
+            Ok(Box::new(stream))
+        }
+        _ => {
+            http_connect(&mut stream, proxy, host, port).await?;
+            Ok(Box::new(stream))
+        }
+    }
+}
+
+/// HTTP 代理的 CONNECT 请求，IPv6 地址加方括号
+fn connect_request(proxy: &ProxyConfig, host: &str, port: u16) -> String {
+    let authority = if host.contains(':') { format!("[{}]:{}", host, port) } else { format!("{}:{}", host, port) };
+    let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+    if let (Some(username), Some(password)) = (&proxy.username, &proxy.password) {
+        let credentials = BASE64.encode(format!("{}:{}", username, password));
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+    }
+    request.push_str("\r\n");
+    request
+}
+
+async fn http_connect(stream: &mut impl WsIo, proxy: &ProxyConfig, host: &str, port: u16) -> Result<(), String> {
+    let request = connect_request(proxy, host, port);
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to send CONNECT request: {}", e))?;
+
+    // 逐字节读取响应头，避免读入隧道中的数据
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() > 8192 {
+            return Err("Proxy response header is too large".to_string());
+        }
+        let byte = stream
+            .read_u8()
+            .await
+            .map_err(|e| format!("Failed to read proxy response: {}", e))?;
+        response.push(byte);
+    }
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(()),
+        _ => Err(format!("Proxy refused CONNECT: {}", status_line)),
+    }
+}
+
+/// SOCKS5 中的字段以一个字节表示长度，最长 255 字节
+fn socks5_length(value: &str, field: &str) -> Result<u8, String> {
+    u8::try_from(value.len()).map_err(|_| format!("SOCKS5 {} is longer than 255 bytes", field))
+}
+
+/// 用户名/密码认证请求（RFC 1929）
+fn socks5_auth_request(username: &str, password: &str) -> Result<Vec<u8>, String> {
+    let mut auth = vec![1, socks5_length(username, "username")?];
+    auth.extend_from_slice(username.as_bytes());
+    auth.push(socks5_length(password, "password")?);
+    auth.extend_from_slice(password.as_bytes());
+    Ok(auth)
+}
+
+/// CONNECT 请求：IP 地址按 IPv4/IPv6 类型发送，域名交给代理解析
+fn socks5_connect_request(host: &str, port: u16) -> Result<Vec<u8>, String> {
+    let mut request = vec![5, 1, 0];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(address)) => {
+            request.push(1);
+            request.extend_from_slice(&address.octets());
+        }
+        Ok(IpAddr::V6(address)) => {
+            request.push(4);
+            request.extend_from_slice(&address.octets());
+        }
+        Err(_) => {
+            request.extend_from_slice(&[3, socks5_length(host, "host name")?]);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    Ok(request)
+}
+
+async fn socks5_handshake(stream: &mut TcpStream, proxy: &ProxyConfig, host: &str, port: u16) -> Result<(), String> {
+    let io_error = |e: std::io::Error| format!("SOCKS5 handshake failed: {}", e);
+    let credentials = proxy.username.as_deref().zip(proxy.password.as_deref());
+
+    // 协商认证方式：0 为无认证，2 为用户名/密码
+    let methods: &[u8] = if credentials.is_some() { &[5, 2, 0, 2] } else { &[5, 1, 0] };
+    stream.write_all(methods).await.map_err(io_error)?;
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await.map_err(io_error)?;
+    match (reply[1], credentials) {
+        (0, _) => {}
+        (2, Some((username, password))) => {
+            let auth = socks5_auth_request(username, password)?;
+            stream.write_all(&auth).await.map_err(io_error)?;
+            stream.read_exact(&mut reply).await.map_err(io_error)?;
+            if reply[1] != 0 {
+                return Err("SOCKS5 authentication failed".to_string());
+            }
+        }
+        _ => return Err("SOCKS5 proxy requires an unsupported authentication method".to_string()),
+    }
+
+    let request = socks5_connect_request(host, port)?;
+    stream.write_all(&request).await.map_err(io_error)?;
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await.map_err(io_error)?;
+    if header[1] != 0 {
+        return Err(format!("SOCKS5 proxy refused connection (code {})", header[1]));
+    }
+    let address_len = match header[3] {
+        1 => 4,
+        4 => 16,
+        3 => usize::from(stream.read_u8().await.map_err(io_error)?),
+        kind => return Err(format!("SOCKS5 proxy returned unknown address type {}", kind)),
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound).await.map_err(io_error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxy(username: Option<&str>, password: Option<&str>) -> ProxyConfig {
+        ProxyConfig {
+            username: username.map(str::to_string),
+            password: password.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn encodes_socks5_connect_requests() {
+        assert_eq!(
+            socks5_connect_request("example.com", 443).unwrap(),
+            [&[5, 1, 0, 3, 11][..], b"example.com", &[1, 187]].concat()
+        );
+        assert_eq!(socks5_connect_request("10.0.0.1", 80).unwrap(), [5, 1, 0, 1, 10, 0, 0, 1, 0, 80]);
+        assert_eq!(
+            socks5_connect_request("::1", 8080).unwrap(),
+            [&[5, 1, 0, 4][..], &[0; 15], &[1, 0x1f, 0x90]].concat()
+        );
+        assert!(socks5_connect_request(&"a".repeat(256), 80).is_err());
+    }
+
+    #[test]
+    fn encodes_socks5_credentials() {
+        assert_eq!(socks5_auth_request("user", "pw").unwrap(), [&[1, 4][..], b"user", &[2], b"pw"].concat());
+        assert_eq!(socks5_auth_request("", "").unwrap(), [1, 0, 0]);
+        assert!(socks5_auth_request("user", &"p".repeat(256)).is_err());
+    }
+
+    #[test]
+    fn encodes_http_connect_requests() {
+        assert_eq!(
+            connect_request(&proxy(None, None), "example.com", 443),
+            "CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n"
+        );
+        assert_eq!(
+            connect_request(&proxy(None, None), "2001:db8::1", 8443),
+            "CONNECT [2001:db8::1]:8443 HTTP/1.1\r\nHost: [2001:db8::1]:8443\r\n\r\n"
+        );
+        // 只有同时设置用户名和密码时才发送认证
+        assert_eq!(
+            connect_request(&proxy(Some("user"), Some("pass")), "10.0.0.1", 80),
+            "CONNECT 10.0.0.1:80 HTTP/1.1\r\nHost: 10.0.0.1:80\r\nProxy-Authorization: Basic dXNlcjpwYXNz\r\n\r\n"
+        );
+        assert!(!connect_request(&proxy(Some("user"), None), "example.com", 80).contains("Proxy-Authorization"));
+    }
+}
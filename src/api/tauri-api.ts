@@ -3,6 +3,7 @@
  * Pure JS implementation - Tauri only serves as a container
  */
 import { isTauri } from '@tauri-apps/api/core';
+import type { ContentInfo } from '@/types/response';
 
 // Helper function to handle authentication
 const handleAuth = (auth: any): Record<string, string> => {
@@ -296,25 +297,40 @@ export const request = {
         user_agent: mergedConfig.userAgent || undefined,
         ca_cert_paths: mergedConfig.caCertPaths || undefined,
         proxy: mergedConfig.proxy || undefined,
+        binaryBody: true,
       };
 
       // Call custom Tauri command
       const response = await invoke<HttpResponse>('send_request', { config: requestConfig });
 
+      // Fetch the body over binary IPC instead of a JSON number array
+      let responseBytes = new Uint8Array(response.body);
+      if (response.bodyId) {
+        try {
+          const buffer = await invoke<ArrayBuffer>('fetch_response_body', { bodyId: response.bodyId });
+          responseBytes = new Uint8Array(buffer);
+        } finally {
+          await invoke('release_response_body', { bodyId: response.bodyId });
+        }
+      }
+
       // Decode body from bytes based on content type
+      // (prefer the backend's charset-aware text or detected encoding, fall back to UTF-8)
       let responseBody: any;
-      const contentType = response.headers['content-type']?.toLowerCase() || '';
-
-      if (contentType.includes('application/json')) {
+      // The backend sniffs the type when Content-Type is missing or generic
+      const contentType = (response.contentInfo?.mimeType || response.headers['content-type'] || '').toLowerCase();
+      const decodeText = () => response.text?.text ?? decodeBytes(responseBytes, response.textEncoding);
+
+      if (response.decodedBody) {
+        // CBOR / MessagePack converted to JSON by the backend
+        responseBody = JSON.parse(response.decodedBody);
+      } else if (contentType.includes('application/json')) {
         // Try to parse as JSON
         try {
-          const textDecoder = new TextDecoder('utf-8');
-          const text = textDecoder.decode(new Uint8Array(response.body));
-          responseBody = JSON.parse(text);
+          responseBody = JSON.parse(decodeText());
         } catch (e) {
           // Fallback to text if JSON parsing fails
-          const textDecoder = new TextDecoder('utf-8');
-          responseBody = textDecoder.decode(new Uint8Array(response.body));
+          responseBody = decodeText();
         }
       } else if (
         contentType.includes('application/octet-stream') ||
@@ -323,11 +339,10 @@ export const request = {
         contentType.includes('audio/')
       ) {
         // Keep as byte array for binary content
-        responseBody = response.body;
+        responseBody = Array.from(responseBytes);
       } else {
         // Decode as text for HTML, XML, plain text, etc.
-        const textDecoder = new TextDecoder('utf-8');
-        responseBody = textDecoder.decode(new Uint8Array(response.body));
+        responseBody = decodeText();
       }
 
       return {
@@ -337,6 +352,7 @@ export const request = {
         statusText: response.statusText,
         headers: response.headers,
         body: responseBody,
+        contentInfo: response.contentInfo,
         size: response.size,
         duration: response.duration,
         timestamp: Date.now(),
@@ -348,11 +364,25 @@ export const request = {
   },
 };
 
+// Decode with the charset detected by the backend, falling back to UTF-8 for labels TextDecoder doesn't know
+function decodeBytes(bytes: Uint8Array, encoding?: string | null): string {
+  try {
+    return new TextDecoder(encoding || 'utf-8').decode(bytes);
+  } catch {
+    return new TextDecoder('utf-8').decode(bytes);
+  }
+}
+
 interface HttpResponse {
   status: number;
   statusText: string;
   headers: Record<string, string>;
   body: number[];
+  bodyId?: string | null;
+  text?: { text: string; encoding: string; source: string; malformed: boolean } | null;
+  textEncoding?: string | null;
+  contentInfo?: ContentInfo;
+  decodedBody?: string | null;
   size: number;
   duration: number;
 }
@@ -5,11 +5,19 @@ export interface Response {
   statusText: string;
   headers: Record<string, string>;
   body: any;
+  contentInfo?: ContentInfo;
   size: number;
   duration: number;
   timestamp: number;
 }
 
+// Actual body type reported by the backend (sniffed when Content-Type is missing)
+export interface ContentInfo {
+  mimeType: string;
+  viewer: 'json' | 'xml' | 'html' | 'text' | 'image' | 'pdf' | 'audio' | 'video' | 'hex';
+  sniffed: boolean;
+}
+
 export type ResponseViewType = 'pretty' | 'raw' | 'preview';
 
 export interface Cookie {
@@ -314,6 +314,65 @@ describe('Tauri API', () => {
       expect(typeof response.body).toBe('string')
       expect(response.body).toBe(String.fromCharCode(0x00, 0x01, 0x02, 0x03))
     })
+
+    it('should fetch cached body over binary IPC', async () => {
+      const mockInvoke = vi.fn(async (cmd: string) => {
+        if (cmd === 'fetch_response_body') {
+          return new Uint8Array(Buffer.from('{"id":42}')).buffer
+        }
+        if (cmd === 'release_response_body') {
+          return null
+        }
+        return {
+          status: 200,
+          statusText: 'OK',
+          headers: { 'content-type': 'application/json' },
+          body: [],
+          bodyId: 'body-1',
+          size: 9,
+          duration: 10
+        }
+      })
+
+      vi.doMock('@tauri-apps/api/core', () => ({
+        invoke: mockInvoke
+      }))
+
+      const response = await request.send({
+        method: 'GET',
+        url: 'https://example.com/api/item'
+      })
+
+      expect(mockInvoke.mock.calls[0][1].config.binaryBody).toBe(true)
+      expect(mockInvoke).toHaveBeenCalledWith('fetch_response_body', { bodyId: 'body-1' })
+      expect(mockInvoke).toHaveBeenCalledWith('release_response_body', { bodyId: 'body-1' })
+      expect(response.body).toEqual({ id: 42 })
+    })
+  })
+
+  describe('Charset Decoding', () => {
+    it('should prefer backend-decoded text', async () => {
+      const mockInvoke = vi.fn(async () => ({
+        status: 200,
+        statusText: 'OK',
+        headers: { 'content-type': 'text/plain; charset=GBK' },
+        body: [0xd6, 0xd0, 0xce, 0xc4],
+        text: { text: '中文', encoding: 'GBK', source: 'contentType', malformed: false },
+        size: 4,
+        duration: 10
+      }))
+
+      vi.doMock('@tauri-apps/api/core', () => ({
+        invoke: mockInvoke
+      }))
+
+      const response = await request.send({
+        method: 'GET',
+        url: 'https://example.com/legacy'
+      })
+
+      expect(response.body).toBe('中文')
+    })
   })
 
   describe('Config Merge', () => {